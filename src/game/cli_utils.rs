@@ -4,10 +4,13 @@ use std::{
     fmt::Display, str::FromStr //traits
 };
 
-use super::field::Field;
+use super::{engine::Game, field::{Difficulty, Field, Square, State}, probabilities::Probabilities, scores::Score, theme::Theme};
 
 //DATA
-const FLAG:char = '\u{f024}';
+/// number of lines in the header of board state
+pub const HEADER_HEIGHT:u16 = 4;
+/// number of characters print_board puts left of each row of squares (the row number, and the '#' border)
+pub const BOARD_LEFT_MARGIN:u16 = 3;
 
 /// resets the screen
 pub fn reset_screen() {
@@ -15,33 +18,130 @@ pub fn reset_screen() {
     print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
 }
 
-/// prints the board state to screen
-pub fn print_game_state(field: &Field) {
+/// prints the board state to screen, highlighting the square at the given position if there is one
+pub fn print_game_state(game: &Game, theme: &Theme, highlighted: Option<(u8,u8)>) {
     //print header
-    print_header(field.get_n_mines(), field.get_n_flags(), field.get_dimensions());
+    print_header(game, theme);
     //print board
-    print_board(field);
-    //print instrustions for inputting commands
-    print_command_instructions();
+    print_board(game.field(), theme, highlighted);
+    //remind the player where the instructions for inputting commands are
+    println!("\nEnter 'help' to see the commands.\n");
 }
 /// prints header of board state
-fn print_header(n_mines:usize, n_flags:usize, board_size:u8) {
-    let width = board_size as usize + 6;
+fn print_header(game: &Game, theme: &Theme) {
+    for line in get_header_lines(game, theme) {
+        println!("{}", line);
+    }
+}
+/// gets the lines of the header of board state, centered over the board
+pub fn get_header_lines(game: &Game, theme: &Theme) -> [String; HEADER_HEIGHT as usize] {
+    //DATA
+    let field = game.field();
+    let width = field.get_dimensions().0 as usize + 6;
+
+    return [
+        format!("{:^width$}", "MINESWEEPER"),
+        format!("{:^width$}", format!("seed: {}",get_seed_label(field.get_seed(), field.get_first_check()))),
+        format!("{:^width$}", format!("{}: {:0>3}  time: {:0>3}",theme.glyphs.flag,field.get_n_mines() as isize-field.get_n_flags() as isize,game.elapsed().as_secs())),
+        format!("{:^width$}", match game.get_n_hints() {
+            0 => format!("moves: {}  3BV/s: {:.2}",game.get_n_actions(),game.get_3bv_per_second()),
+            n_hints => format!("moves: {}  hints: {}  3BV/s: {:.2}",game.get_n_actions(),n_hints,game.get_3bv_per_second()),
+        }),
+    ];
+}
+/// prints the stats of a finished game
+pub fn print_result(game: &Game) {
+    let (total_3bv, solved_3bv) = game.get_3bv();
     println!(
-"{title:^width$}
+"time: {:.1}s  moves: {}  hints: {}  3BV: {}/{}  3BV/s: {:.2}",
+        game.elapsed().as_secs_f64(),
+        game.get_n_actions(),
+        game.get_n_hints(),
+        solved_3bv,
+        total_3bv,
+        game.get_3bv_per_second(),
+    );
+}
+/// prints the table of the best scores for a difficulty, marking the highlighted score if it's in there
+pub fn print_scores(difficulty: Difficulty, top_scores: &[&Score], highlighted: Option<&Score>) {
+    println!("HIGH SCORES - {}", difficulty);
+    if top_scores.is_empty() {
+        println!("  no games won yet\n");
+        return;
+    }
 
-{flagText:^width$}",
-    title = "MINESWEEPER" as &str,
-    flagText = format!("{}: {:0>3}",FLAG,n_mines as isize-n_flags as isize),
+    println!("   {:>2}  {:>8}  {:<10}  {:<20}  seed", "#", "time", "date", "name");
+    for (rank, score) in top_scores.iter().enumerate() {
+        println!(
+            "{marker}  {rank:>2}  {time:>7.1}s  {date:<10}  {name:<20}  {seed}",
+            marker = if highlighted == Some(*score) {'>'} else {' '},
+            rank = rank + 1,
+            time = score.time.as_secs_f64(),
+            date = score.get_date(),
+            name = score.name,
+            seed = get_seed_label(score.seed, score.first_check),
+        );
+    }
+    println!();
+}
+/// gets the seed along with the first check, which together place the same mines, like "42 (first check e4)"
+fn get_seed_label(seed: u64, first_check: Option<(u8,u8)>) -> String {
+    return match first_check {
+        Some((x_pos,y_pos)) => format!("{} (first check {}{})", seed, get_column_label(x_pos), y_pos),
+        None => seed.to_string(),
+    };
+}
+/// prints board, in the colours of the theme, highlighting the square at the given position if there is one
+pub fn print_board(field: &Field, theme: &Theme, highlighted: Option<(u8,u8)>) {
+    println!("{}", get_board(field, &|square| {
+        if highlighted == Some(square.get_position()) {
+            return theme.paint_highlighted(square);
+        }
+        return theme.paint(square);
+    }));
+}
+/// gets the board with how likely every hidden square is to be a mine in place of its icon, heat map coloured,
+/// followed by a key and the hidden squares least likely to be mines
+pub fn get_probabilities_board(field: &Field, theme: &Theme, probabilities: &Probabilities) -> String {
+    //DATA
+    let board = get_board(field, &|square| theme.paint_chance(square, probabilities));
+    //the unflagged squares least likely to be mines, the ones to check when there's nothing certain
+    let mut safest:Vec<((u8,u8), f64)> = field.get_grid().iter().flatten()
+        .filter(|square| *square.get_state() != State::FLAGGED)
+        .filter_map(|square| Some((square.get_position(), probabilities.get(square.get_position().0, square.get_position().1)?)))
+        .collect();
+    safest.sort_by(|(a_position, a), (b_position, b)| a.total_cmp(b).then((a_position.1,a_position.0).cmp(&(b_position.1,b_position.0))));
+
+    return format!(
+"{board}
+{key}
+least likely to be mines: {safest}",
+        key = get_probabilities_key(theme, probabilities),
+        safest = safest.iter().take(3)
+            .map(|((x_pos,y_pos), probability)| format!("{}{} ({:.1}%)", get_column_label(*x_pos), y_pos, probability * 100.0))
+            .collect::<Vec<String>>()
+            .join(", "),
     );
 }
-/// prints board
-pub fn print_board(field: &Field) {
-    println!(
-"  #{column_letters}#  
+/// gets the key to the chances drawn by get_probabilities_board
+pub fn get_probabilities_key(theme: &Theme, probabilities: &Probabilities) -> String {
+    return format!(
+        "chance of a mine, in tenths: {} certainly safe, 0 under 10%, 1 under 20%, ... 9 90% or more, {} certainly a mine{}",
+        theme.paint_probability(0.0),
+        theme.paint_probability(1.0),
+        if probabilities.is_exact() {""} else {" (estimated)"},
+    );
+}
+/// gets the lines of a board, with every square drawn by paint
+fn get_board(field: &Field, paint: &dyn Fn(&Square) -> String) -> String {
+    return format!(
+"{column_labels}
 {grid}
-  #{column_letters}#  ",
-        column_letters = (0u8..field.get_dimensions()).fold(String::new(), |mut acc, i| {acc.push((i+97) as char); acc}),
+{column_labels}",
+        column_labels = get_column_label_lines(field).iter()
+            .map(|line| format!("{:margin$}#{}#{:margin$}", "", line, "", margin = BOARD_LEFT_MARGIN as usize - 1))
+            .collect::<Vec<String>>()
+            .join("\n"),
         grid = field.get_grid().iter().enumerate().fold(String::new(), //fold contents of grid into a single string
             |mut acc, row_tup| { //for every row
                 //DATA 
@@ -51,11 +151,12 @@ pub fn print_board(field: &Field) {
                 //add the row number, and contents to acc
                 acc.push_str(
                     format!(
-                        "{num:<2}#{}#{num:>2}",
+                        "{num:<margin$}#{}#{num:>margin$}",
                         row.iter().fold(String::new(), |mut nested_acc, square| {
-                            nested_acc.push(square.get_icon());
+                            nested_acc.push_str(&paint(square));
                             return nested_acc;
                         }), //fold the contents of the row into a single string
+                        margin = BOARD_LEFT_MARGIN as usize - 1,
                     ).as_str()
                 );
 
@@ -65,23 +166,84 @@ pub fn print_board(field: &Field) {
                 return acc;
             }
         ),
-    )
+    );
 }
 
-fn print_command_instructions() {println!(
-"
-Commands:
+/// gets the number of lines print_board puts above the first row of squares (the column labels)
+pub fn get_board_top_margin(field: &Field) -> u16 {
+    return get_column_label(field.get_dimensions().0.saturating_sub(1)).len() as u16;
+}
+/// gets the lines labelling the columns of the field, with one character per column,
+/// labels longer than a letter are read from top to bottom
+pub fn get_column_label_lines(field: &Field) -> Vec<String> {
+    //DATA
+    let labels:Vec<String> = (0..field.get_dimensions().0).map(get_column_label).collect();
+    let n_lines = get_board_top_margin(field) as usize;
+
+    return (0..n_lines).map(|line| labels.iter().map(|label| {
+        //shorter labels are aligned to the bottom
+        let padding = n_lines - label.len();
+        if line < padding {' '} else {label.as_bytes()[line - padding] as char}
+    }).collect()).collect();
+}
+/// gets the label of the column at the given x position, spreadsheet style: a to z, then aa, ab, and so on
+pub fn get_column_label(x_pos: u8) -> String {
+    //DATA
+    let mut label = String::new();
+    let mut n = x_pos as usize + 1;
+
+    while n > 0 {
+        n -= 1;
+        label.insert(0, (b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    return label;
+}
+/// gets the x position of the column with the given label (in any case), None if it isn't made of letters
+pub fn parse_column_label(label: &str) -> Option<usize> {
+    if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    return label.to_ascii_lowercase().bytes()
+        .try_fold(0usize, |acc, c| acc.checked_mul(26)?.checked_add((c - b'a') as usize + 1))
+        .map(|n| n - 1);
+}
+
+/// gets the instructions for inputting commands
+pub fn get_command_instructions() -> String {String::from(
+"Commands:
  - Check (prefix: 'C'): check if the following square is a mine or not
-                        you lose the game is it's a mine
+                        you lose the game if it's a mine
  - Flag (prefix: 'F'):  adds a flag to the following square, 
                         or removes it if one is already there
+                        (with --marks the flag becomes a question mark first)
+ - Chord (prefix: 'D'): checks every unflagged square around the following number,
+                        if it has as many flags around it as its number
+                        (checking a number that's already visible does the same)
 The format for commands is as follows:
-{{command prefix}}{{column letter}}{{row number}}
+{{command prefix}}{{column letters}}{{row number}} or {{command prefix}}{{column number}},{{row number}}
 
-For example, the command Fa0 would add a flag to the top left corner.
+For example, the commands Fa0, f 0a and F0,0 would all add a flag to the top left corner.
+Several commands can be entered at once, like fa1 fb2 cc3.
 
+Other commands:
+ - undo, redo:        take back the last move, or play it again
+ - hint:              point out a square that's certainly safe (or a mine), worked out from what's visible
+ - probs:             show how likely every hidden square is to be a mine, for when you have to guess
+                      (this counts as a hint)
+ - save [file]:       save the game for later (to the given file, if there is one)
+ - restart:           start over on the same field
+ - new [difficulty]:  start a new game, on the given difficulty or the same one
+ - reveal-all:        give up, and see where the mines were
+ - help:              show these instructions
+ - quit:              stop playing, the game is autosaved so it can be resumed
 "
-);}
+)}
+
+/// asks the player a yes or no question, anything but yes (including the end of input) is no
+pub fn confirm(prompt: &str) -> bool {
+    return matches!(get_string_from_user_input(prompt), Ok(s) if s.eq_ignore_ascii_case("y") || s.eq_ignore_ascii_case("yes"));
+}
 
 /// gets a string from user input
 pub fn get_string_from_user_input(prompt: &str) -> Result<String, Box<dyn Error>> {
@@ -96,10 +258,15 @@ pub fn get_string_from_user_input(prompt: &str) -> Result<String, Box<dyn Error>
     //read user input from standard input, and store it to raw_input, then return it or an error as needed
     raw_input.clear(); //clear input
     match io::stdin().read_line(&mut raw_input) {
+        Ok(0) => {println!(); return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "no more input").into())},
         Ok(_num_bytes_read) => return Ok(String::from(raw_input.trim())),
         Err(err) => return Err(format!("ERROR: CANNOT READ INPUT!: {}", err).into()),
     }
 }
+/// returns whether the error is because there's no more user input to read (e.g. stdin was closed)
+pub fn is_end_of_input(err: &(dyn Error + 'static)) -> bool {
+    return err.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::UnexpectedEof);
+}
 /// generic function to get a number from the passed string (user input)
 /// pass a min lower  than the max to have minimun and maximun bounds
 /// pass a min higher than the max to only have a minumum bound
@@ -107,16 +274,12 @@ pub fn get_string_from_user_input(prompt: &str) -> Result<String, Box<dyn Error>
 /// 
 /// Errors:
 /// no number on user input
-pub fn _get_number_from_input<T:Display + PartialOrd + FromStr>(prompt: &str, min:T, max:T) -> Result<T, Box<dyn Error>> {
-    //DATA
-    let raw_input: String;
-    let processed_input: String;
-
-    
+pub fn get_number_from_input<T:Display + PartialOrd + FromStr>(prompt: &str, min:T, max:T) -> Result<T, Box<dyn Error>> {
     //input looop
-    raw_input = loop {
+    let raw_input: String = loop {
         match get_string_from_user_input(prompt) {
             Ok(input) => break input,
+            Err(e) if is_end_of_input(e.as_ref()) => return Err(e),
             Err(e) => {
                 eprintln!("{}",e);
                 continue;
@@ -125,7 +288,7 @@ pub fn _get_number_from_input<T:Display + PartialOrd + FromStr>(prompt: &str, mi
     };
 
     //filter out num-numeric characters from user input
-    processed_input = raw_input.chars().filter(|c| c.is_numeric()).collect();
+    let processed_input: String = raw_input.chars().filter(|c| c.is_numeric()).collect();
 
     //from input, try to read a number
     match processed_input.trim().parse() {
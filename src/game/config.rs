@@ -1,41 +1,196 @@
-use std::error::Error;
+use std::{env, error::Error, fs, path::PathBuf};
 
 use super::cli_utils;
-use super::field::{Difficulty,Field};
+use super::engine::{Game, Rules};
+use super::glyphs::GlyphSet;
+use super::save;
+use super::theme::Theme;
+pub use super::field::Difficulty;
 
+/// settings given on the command line, anything left as None is asked for interactively
+#[derive(Default, Clone)]
+pub struct Options {
+    pub difficulty:Option<Difficulty>,
+    pub safe_neighbours:Option<bool>,
+    pub strict_flags:Option<bool>,
+    /// whether flags can be turned into question marks
+    pub marks:bool,
+    /// whether a lost game can be undone (keeping it off the high scores)
+    pub practice:bool,
+    /// whether the mines are placed so the field can be cleared without guessing
+    pub no_guess:bool,
+    pub seed:Option<u64>,
+    /// a saved game to resume
+    pub load:Option<PathBuf>,
+    pub no_clear:bool,
+    /// play with the cursor driven terminal UI instead of typing commands
+    pub tui:bool,
+    /// the glyphs to draw the field with, if None it's read from the config file
+    pub glyphs:Option<GlyphSet>,
+    /// print the high scores instead of playing
+    pub show_scores:bool,
+}
 
 /// handles setup for the game
 pub struct Config {
-    pub field:Field,
+    pub game:Game,
+    /// whether to leave the screen as is instead of clearing it every round
+    pub no_clear:bool,
+    /// whether to play with the cursor driven terminal UI
+    pub tui:bool,
+    /// the glyphs and colours the field is drawn with
+    pub theme:Theme,
 }
 impl Config {
-    /// creates and returns a new Config from user input
-    pub fn new() -> Result<Config, Box<dyn Error>> {
+    /// creates and returns a new Config from the given options,
+    /// if no difficulty (or saved game) is given the game is set up from user input instead
+    pub fn new(options:Options) -> Result<Config, Box<dyn Error>> {
         //DATA
-        let difficulty: Difficulty;
-        let mut config: Config = Config {
-            field:Field::new(),
+        let mut rules: Rules = Rules {
+            safe_neighbours: options.safe_neighbours.unwrap_or(true),
+            strict_flags: options.strict_flags.unwrap_or(false),
+            marks: options.marks,
+            practice: options.practice,
+            no_guess: options.no_guess,
+        };
+        let glyphs = match options.glyphs {
+            Some(glyphs) => glyphs,
+            None => match read_config_value("glyphs")? {
+                Some(name) => GlyphSet::from_name(&name).ok_or(format!("unknown glyph set '{}' in the config file", name))?,
+                None => GlyphSet::default(),
+            },
         };
+        let theme = Theme::detect(glyphs);
+
+        //resume a saved game
+        if let Some(path) = options.load {
+            return Ok(Config {
+                game: save::load_game(&path).map_err(|e| format!("couldn't load {}: {}", path.display(), e))?,
+                no_clear: options.no_clear,
+                tui: options.tui,
+                theme,
+            });
+        }
+
+        //pick a seed if none is given, so the field can always be played again
+        let seed = options.seed.unwrap_or_else(rand::random);
+
+        //a game set up from the command line doesn't ask anything
+        if let Some(difficulty) = options.difficulty {
+            return Ok(Config {
+                game: Game::new(difficulty, seed, rules)?,
+                no_clear: options.no_clear,
+                tui: options.tui,
+                theme,
+            });
+        }
 
         //get data from user input
         //input loop
-        loop { match cli_utils::get_string_from_user_input("DIFFICULTY (B = Beginner, I = Intermediate, A = Advanced): ") {
+        let difficulty = loop { match cli_utils::get_string_from_user_input("DIFFICULTY (B = Beginner, I = Intermediate, A = Advanced, E = Expert, C = Custom): ") {
             Ok (s) => {
-                match s.to_ascii_lowercase().chars().nth(0) {
-                    Some('b') => difficulty = Difficulty::BEGINNER,
-                    Some('i') => difficulty = Difficulty::INTERMEDIATE,
-                    Some('a') => difficulty = Difficulty::ADVANCED,
+                let difficulty = match s.to_ascii_lowercase().chars().next() {
+                    Some('b') => Difficulty::BEGINNER,
+                    Some('i') => Difficulty::INTERMEDIATE,
+                    Some('a') => Difficulty::ADVANCED,
+                    Some('e') => Difficulty::EXPERT,
+                    Some('c') => match Config::get_custom_difficulty_from_user_input() {
+                        Ok(difficulty) => difficulty,
+                        Err(e) if cli_utils::is_end_of_input(e.as_ref()) => return Err(e),
+                        Err(e) => {eprintln!("{}",e); continue;},
+                    },
                     _ => {eprintln!("invalid difficulty"); continue;},
+                };
+                //make sure a field can actually be made
+                match difficulty.validate() {
+                    Ok(()) => break difficulty,
+                    Err(e) => {eprintln!("invalid difficulty: {}",e); continue;},
+                }
+            },
+            Err(e) if cli_utils::is_end_of_input(e.as_ref()) => return Err(e),
+            Err(e) => eprintln!("{}",e),
+        }};
+
+        if options.safe_neighbours.is_none() { loop { match cli_utils::get_string_from_user_input("FIRST CHECK (S = only the Square is safe, A = the whole Area around it is safe): ") {
+            Ok (s) => {
+                match s.to_ascii_lowercase().chars().next() {
+                    Some('s') => rules.safe_neighbours = false,
+                    Some('a') | None => rules.safe_neighbours = true,
+                    _ => {eprintln!("invalid option"); continue;},
                 }
                 break;
             },
+            Err(e) if cli_utils::is_end_of_input(e.as_ref()) => return Err(e),
             Err(e) => eprintln!("{}",e),
-        }}
+        }}}
 
-        //populate field
-        config.field.populate(difficulty);
+        if options.strict_flags.is_none() { loop { match cli_utils::get_string_from_user_input("FLAGS (O = Optional, S = Strict, every mine must be flagged to win): ") {
+            Ok (s) => {
+                match s.to_ascii_lowercase().chars().next() {
+                    Some('o') | None => rules.strict_flags = false,
+                    Some('s') => rules.strict_flags = true,
+                    _ => {eprintln!("invalid option"); continue;},
+                }
+                break;
+            },
+            Err(e) if cli_utils::is_end_of_input(e.as_ref()) => return Err(e),
+            Err(e) => eprintln!("{}",e),
+        }}}
 
-        //return new config
-        return Ok(config);
+        //return new config, mines are placed when the first square is checked
+        return Ok(Config {
+            game: Game::new(difficulty, seed, rules)?,
+            no_clear: options.no_clear,
+            tui: options.tui,
+            theme,
+        });
     }
+
+    /// gets the dimensions and mine count of a custom field from user input
+    fn get_custom_difficulty_from_user_input() -> Result<Difficulty, Box<dyn Error>> {
+        let width:u8 = cli_utils::get_number_from_input(&format!("WIDTH (1-{}): ", Difficulty::MAX_WIDTH), 1, Difficulty::MAX_WIDTH)?;
+        let height:u8 = cli_utils::get_number_from_input(&format!("HEIGHT (1-{}): ", Difficulty::MAX_HEIGHT), 1, Difficulty::MAX_HEIGHT)?;
+        let mines:usize = cli_utils::get_number_from_input("MINES: ", 1, 0)?;
+        return Ok(Difficulty::CUSTOM { width, height, mines });
+    }
+}
+
+/// gets the directory the game keeps its files in (scores and the like),
+/// $XDG_DATA_HOME/rust-cli-minesweeper, or ~/.local/share/rust-cli-minesweeper if that isn't set
+pub fn get_data_dir() -> Option<PathBuf> {
+    let data_home = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".local").join("share"),
+    };
+    return Some(data_home.join("rust-cli-minesweeper"));
+}
+
+/// gets the path of the config file,
+/// $XDG_CONFIG_HOME/rust-cli-minesweeper/config, or ~/.config/rust-cli-minesweeper/config if that isn't set
+pub fn get_config_path() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    return Some(config_home.join("rust-cli-minesweeper").join("config"));
+}
+
+/// reads the value of a setting from the config file, made of "key = value" lines and '#' comments,
+/// None if it isn't set (or there's no config file)
+///
+/// Errors:
+/// the config file exists but can't be read
+fn read_config_value(key:&str) -> Result<Option<String>, Box<dyn Error>> {
+    let path = match get_config_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(None),
+    };
+    let contents = fs::read_to_string(&path).map_err(|e| format!("couldn't read {}: {}", path.display(), e))?;
+
+    return Ok(contents
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(line_key,_)| line_key.trim() == key)
+        .map(|(_,value)| String::from(value.trim())));
 }
@@ -0,0 +1,797 @@
+use std::{error::Error, fmt, mem, time::{Duration, Instant}};
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::field::{Difficulty, Field, GameStatus, State};
+use super::probabilities::{self, Probabilities};
+use super::solver::{self, View};
+
+//DATA
+/// the most layouts tried for a no-guess field, before giving up
+const MAX_NO_GUESS_ATTEMPTS: usize = 500;
+/// the highest share of the squares that can be mines on a no-guess field,
+/// above it hardly any layout can be cleared without guessing (expert is just under)
+const MAX_NO_GUESS_DENSITY: f64 = 0.21;
+
+/**
+ * the game engine, a pure state machine over a field:
+ * actions are applied to it one at a time, and it reports what they did,
+ * without doing any terminal I/O itself
+ */
+pub struct Game {
+    field: Field,
+    rules: Rules,
+    status: GameStatus,
+    /// the number of actions that changed the field, not counting those undone
+    n_actions: usize,
+    /// time played before the game was saved and loaded again
+    elapsed_before: Duration,
+    started_at: Option<Instant>,
+    finished_at: Option<Instant>,
+    /// the field before every action that changed it, most recent last, for undo
+    history: Vec<Field>,
+    /// the fields undone, most recently undone last, for redo
+    undone: Vec<Field>,
+    /// whether the game was practiced, by undoing a loss (only allowed in practice mode)
+    /// or restarting it once the mines were placed, so it can't go on the high scores
+    practiced: bool,
+    /// whether the player gave up, revealing the mines
+    given_up: bool,
+    /// the number of hints given
+    n_hints: usize,
+}
+impl Game {
+    /// creates a new game on an empty field for the given difficulty,
+    /// the mines are placed from the given seed when the first square is checked
+    ///
+    /// Errors:
+    /// a field can't be made for the difficulty
+    /// the field has too many mines to be made without guessing, if the rules call for that
+    pub fn new(difficulty:Difficulty, seed:u64, rules:Rules) -> Result<Game, GameError> {
+        difficulty.validate().map_err(GameError::INVALID_DIFFICULTY)?;
+        let (width,height) = difficulty.get_dimensions();
+        if rules.no_guess && difficulty.get_n_mines() as f64 > MAX_NO_GUESS_DENSITY * (width as usize * height as usize) as f64 {
+            return Err(GameError::INVALID_DIFFICULTY(format!(
+                "too many mines for a field that can be cleared without guessing, at most {}% of the squares can be mines",
+                (MAX_NO_GUESS_DENSITY * 100.0) as usize
+            )));
+        }
+
+        let mut field = Field::new();
+        field.initialize(difficulty, seed);
+
+        return Ok(Game {
+            field,
+            rules,
+            status: GameStatus::PLAYING,
+            n_actions: 0,
+            elapsed_before: Duration::ZERO,
+            started_at: None,
+            finished_at: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+            practiced: false,
+            given_up: false,
+            n_hints: 0,
+        });
+    }
+
+    /// resumes a game (e.g. a saved one) on the given field, 
+    /// that has already had n_actions applied to it (and n_hints given) over the given time,
+    /// the actions before it can't be undone
+    pub fn restore(field:Field, rules:Rules, n_actions:usize, n_hints:usize, elapsed:Duration, practiced:bool) -> Game {
+        let status = field.get_status(rules.strict_flags);
+        let now = Instant::now();
+        return Game {
+            //the timer keeps running if the mines were already placed
+            started_at: if field.is_populated() {Some(now)} else {None},
+            finished_at: if status != GameStatus::PLAYING {Some(now)} else {None},
+            field,
+            rules,
+            status,
+            n_actions,
+            elapsed_before: elapsed,
+            history: Vec::new(),
+            undone: Vec::new(),
+            practiced,
+            given_up: false,
+            n_hints,
+        };
+    }
+
+    /// applies the given action to the game, and returns what it did
+    ///
+    /// Errors:
+    /// the game is already over
+    /// the action is for a square outside the field
+    /// the action is the first check of a no-guess game, and no field that can be cleared without guessing was found
+    pub fn apply(&mut self, action:Action) -> Result<Outcome, GameError> {
+        //DATA
+        let (x_pos,y_pos) = action.get_position();
+
+        //can't play a finished game
+        if self.status != GameStatus::PLAYING {
+            return Err(GameError::GAME_OVER);
+        }
+        //can't play outside the field
+        let state = match self.field.get_square_at(x_pos as isize, y_pos as isize) {
+            Some(square) => *square.get_state(),
+            None => return Err(GameError::OUT_OF_BOUNDS(x_pos, y_pos)),
+        };
+
+        //handle action, remembering the field as it was
+        let before = self.field.clone();
+        let outcome = match action {
+            Action::FLAG(..) => self.toggle_flag(x_pos, y_pos),
+            Action::CHECK(..) if State::VISIBLE.eq(&state) => self.chord(x_pos, y_pos),
+            Action::CHECK(..) => self.check(x_pos, y_pos)?,
+            Action::CHORD(..) => self.chord(x_pos, y_pos),
+        };
+        //only moves that did something count
+        if outcome != Outcome::UNCHANGED {
+            self.n_actions += 1;
+            self.history.push(before);
+            self.undone.clear();
+        }
+
+        //end the game once it's won or lost
+        self.status = self.field.get_status(self.rules.strict_flags);
+        if self.status != GameStatus::PLAYING {
+            self.finished_at = Some(Instant::now());
+        }
+        return Ok(match self.status {
+            GameStatus::PLAYING => outcome,
+            GameStatus::LOST => {
+                // go through every mine and make it visible
+                self.field.show_mines();
+                Outcome::LOST
+            },
+            GameStatus::WON => {
+                // make the whole field visible
+                self.field.show_field();
+                Outcome::WON
+            },
+        });
+    }
+
+    /// takes back the last action that changed the field, which then doesn't count as a move anymore
+    ///
+    /// Errors:
+    /// there's nothing left to undo
+    /// the game is won, or lost outside of practice mode
+    pub fn undo(&mut self) -> Result<(), GameError> {
+        //only practice games can be taken back after hitting a mine (but not after giving up)
+        match self.status {
+            GameStatus::PLAYING => {},
+            GameStatus::LOST if self.rules.practice && !self.given_up => {},
+            _ => return Err(GameError::GAME_OVER),
+        }
+        let field = self.history.pop().ok_or(GameError::NOTHING_TO_UNDO)?;
+
+        if self.status == GameStatus::LOST {
+            self.practiced = true;
+        }
+        self.undone.push(mem::replace(&mut self.field, field));
+        self.n_actions -= 1;
+        self.update_status();
+        return Ok(());
+    }
+
+    /// applies the last undone action again, counting it as a move again
+    ///
+    /// Errors:
+    /// nothing was undone since the last action
+    pub fn redo(&mut self) -> Result<(), GameError> {
+        let field = self.undone.pop().ok_or(GameError::NOTHING_TO_REDO)?;
+        self.history.push(mem::replace(&mut self.field, field));
+        self.n_actions += 1;
+        self.update_status();
+        return Ok(());
+    }
+
+    /// starts the game over on the same field, with the same mines if they were already placed,
+    /// which makes it practiced as the player knows where they are
+    pub fn restart(&mut self) {
+        //DATA
+        let mut field = Field::new();
+        field.initialize(*self.field.get_difficulty(), self.field.get_seed());
+
+        if self.field.is_populated() {
+            field.place_mines(self.field.get_mines());
+            field.set_first_check(self.field.get_first_check());
+            self.practiced = true;
+        }
+        self.field = field;
+        self.status = GameStatus::PLAYING;
+        self.n_actions = 0;
+        self.n_hints = 0;
+        self.elapsed_before = Duration::ZERO;
+        self.started_at = if self.field.is_populated() {Some(Instant::now())} else {None};
+        self.finished_at = None;
+        self.history.clear();
+        self.undone.clear();
+        self.given_up = false;
+    }
+
+    /// gives up the game, losing it and showing where the mines were
+    ///
+    /// Errors:
+    /// the game is already over
+    /// no square has been checked yet, so there are no mines to show
+    pub fn give_up(&mut self) -> Result<(), GameError> {
+        if self.status != GameStatus::PLAYING {
+            return Err(GameError::GAME_OVER);
+        }
+        if !self.field.is_populated() {
+            return Err(GameError::NOT_STARTED);
+        }
+
+        self.field.show_mines();
+        self.given_up = true;
+        self.update_status();
+        return Ok(());
+    }
+
+    /// works out a hint from what the player can see: a square that's certainly safe (one that isn't flagged if there's any),
+    /// or if there's none, a square that's certainly a mine and isn't flagged yet
+    ///
+    /// Errors:
+    /// the game is already over
+    /// no square has been checked yet, so there's nothing to work from (but any square is safe)
+    pub fn hint(&mut self) -> Result<Hint, GameError> {
+        if self.status != GameStatus::PLAYING {
+            return Err(GameError::GAME_OVER);
+        }
+        if !self.field.is_populated() {
+            return Err(GameError::NOT_STARTED);
+        }
+
+        let deductions = solver::solve(&View::from_field(&self.field));
+        let is_flagged = |(x_pos,y_pos):&(u8,u8)| self.field.get_square_at(*x_pos as isize, *y_pos as isize).is_some_and(|square| *square.get_state() == State::FLAGGED);
+        let safe = deductions.safe.iter().find(|square| !is_flagged(square)).or(deductions.safe.first());
+        let hint = match (safe, deductions.mines.iter().find(|square| !is_flagged(square))) {
+            (Some((x_pos,y_pos)), _) => Hint::SAFE(*x_pos, *y_pos),
+            (None, Some((x_pos,y_pos))) => Hint::MINE(*x_pos, *y_pos),
+            (None, None) => Hint::GUESS,
+        };
+        self.n_hints += 1;
+        return Ok(hint);
+    }
+
+    /// works out how likely every hidden square is to be a mine from what the player can see,
+    /// which counts as a hint
+    ///
+    /// Errors:
+    /// the game is already over
+    /// no square has been checked yet, so there's nothing to work from
+    /// the field is too complex to work them out
+    pub fn probabilities(&mut self) -> Result<Probabilities, GameError> {
+        if self.status != GameStatus::PLAYING {
+            return Err(GameError::GAME_OVER);
+        }
+        if !self.field.is_populated() {
+            return Err(GameError::NOT_STARTED);
+        }
+
+        let probabilities = probabilities::get_probabilities(&View::from_field(&self.field)).ok_or(GameError::TOO_COMPLEX)?;
+        self.n_hints += 1;
+        return Ok(probabilities);
+    }
+
+    /// updates the status to that of the field after undoing or redoing,
+    /// the timer stops while the game is over, and keeps going once it isn't
+    fn update_status(&mut self) {
+        let was_playing = self.status == GameStatus::PLAYING;
+        self.status = self.field.get_status(self.rules.strict_flags);
+        match (was_playing, self.status == GameStatus::PLAYING) {
+            (true, false) => self.finished_at = Some(Instant::now()),
+            (false, true) => {
+                //the time spent looking at the lost game doesn't count
+                if let (Some(started_at), Some(finished_at)) = (self.started_at, self.finished_at) {
+                    self.elapsed_before += finished_at - started_at;
+                    self.started_at = Some(Instant::now());
+                }
+                self.finished_at = None;
+            },
+            _ => {},
+        }
+    }
+
+    /// checks the hidden square at the given position, placing the mines first if this is the first check
+    ///
+    /// Errors:
+    /// no field that can be cleared without guessing was found, if the rules call for one
+    fn check(&mut self, x_pos:u8, y_pos:u8) -> Result<Outcome, GameError> {
+        //only hidden squares can be checked (question marks are still hidden)
+        if let Some(square) = self.field.get_square_at(x_pos as isize, y_pos as isize) {
+            if !square.get_state().is_hidden() {
+                return Ok(Outcome::UNCHANGED);
+            }
+        }
+
+        // the first check places the mines, keeping the checked square free of them, and starts the timer
+        if !self.field.is_populated() {
+            if self.rules.no_guess {
+                self.populate_without_guessing(x_pos, y_pos)?;
+            } else {
+                self.field.populate(x_pos, y_pos, self.rules.safe_neighbours);
+            }
+            self.started_at = Some(Instant::now());
+        }
+
+        let n_visible = self.field.get_n_visible();
+        self.field.reveal(x_pos as isize, y_pos as isize);
+        return Ok(Outcome::REVEALED(self.field.get_n_visible() - n_visible));
+    }
+
+    /// places the mines so that the field can be cleared from the first check at the given position without guessing,
+    /// drawing layouts from the seed until the solver can clear one (the first is the one populate would place)
+    ///
+    /// Errors:
+    /// none of the layouts tried can be cleared without guessing
+    fn populate_without_guessing(&mut self, x_pos:u8, y_pos:u8) -> Result<(), GameError> {
+        //DATA
+        let mut rng = ChaCha8Rng::seed_from_u64(self.field.get_seed());
+
+        for _ in 0..MAX_NO_GUESS_ATTEMPTS {
+            let mut field = self.field.clone();
+            field.populate_with(x_pos, y_pos, self.rules.safe_neighbours, &mut rng);
+            if solver::can_clear(&field, x_pos, y_pos) {
+                self.field = field;
+                return Ok(());
+            }
+        }
+        return Err(GameError::NO_GUESS_FIELD_NOT_FOUND(MAX_NO_GUESS_ATTEMPTS));
+    }
+
+    /// chords the visible square at the given position
+    fn chord(&mut self, x_pos:u8, y_pos:u8) -> Outcome {
+        let n_visible = self.field.get_n_visible();
+        self.field.chord(x_pos as isize, y_pos as isize);
+        return match self.field.get_n_visible() - n_visible {
+            0 => Outcome::UNCHANGED,
+            n_revealed => Outcome::REVEALED(n_revealed),
+        };
+    }
+
+    /// adds a flag to the hidden square at the given position, or removes the one already there,
+    /// going through a question mark first if marks are on
+    fn toggle_flag(&mut self, x_pos:u8, y_pos:u8) -> Outcome {
+        let square = match self.field.get_square_at_mut(x_pos as isize, y_pos as isize) {
+            Some(square) => square,
+            None => return Outcome::UNCHANGED,
+        };
+
+        return match square.get_state() {
+            //with marks on, flags become question marks before they're removed
+            State::FLAGGED if self.rules.marks => {
+                square.set_state(State::MARKED);
+                self.field.decrement_n_flags();
+                Outcome::MARKED
+            },
+            State::FLAGGED => {
+                square.set_state(State::HIDDEN);
+                self.field.decrement_n_flags();
+                Outcome::UNFLAGGED
+            },
+            State::MARKED => {
+                square.set_state(State::HIDDEN);
+                Outcome::UNMARKED
+            },
+            State::HIDDEN => {
+                square.set_state(State::FLAGGED);
+                self.field.increment_n_flags();
+                Outcome::FLAGGED
+            },
+            //visible squares can't be flagged, and the rest only exist once the game is over
+            State::VISIBLE | State::DETONATED | State::WRONG_FLAG => Outcome::UNCHANGED,
+        };
+    }
+
+    /// gets the time spent playing, from the first check until the game ended (or now, if it hasn't)
+    pub fn elapsed(&self) -> Duration {
+        return self.elapsed_before + match (self.started_at, self.finished_at) {
+            (Some(started_at), Some(finished_at)) => finished_at - started_at,
+            (Some(started_at), None) => started_at.elapsed(),
+            _ => Duration::ZERO,
+        };
+    }
+    /// gets the 3BV of the field as (total, solved so far), 0 until the mines are placed
+    pub fn get_3bv(&self) -> (usize,usize) {
+        if !self.field.is_populated() {
+            return (0,0);
+        }
+        return self.field.get_3bv();
+    }
+    /// gets the 3BV solved per second of play, a measure of how efficiently the game is played
+    pub fn get_3bv_per_second(&self) -> f64 {
+        let seconds = self.elapsed().as_secs_f64();
+        if seconds <= 0.0 {
+            return 0.0;
+        }
+        return self.get_3bv().1 as f64 / seconds;
+    }
+
+    //getters
+    /// get the number of actions that changed the field so far, not counting those undone
+    pub fn get_n_actions(&self) -> usize {self.n_actions}
+    /// get the number of hints given so far
+    pub fn get_n_hints(&self) -> usize {self.n_hints}
+    /// get the status of the game
+    pub fn status(&self) -> &GameStatus {&self.status}
+    /// get the field the game is played on
+    pub fn field(&self) -> &Field {&self.field}
+    /// get the rules the game is played by
+    pub fn rules(&self) -> &Rules {&self.rules}
+    /// get whether the game was practiced (a loss undone, or restarted), which keeps it off the high scores
+    pub fn is_practiced(&self) -> bool {self.practiced}
+    /// get whether the player gave up
+    pub fn is_given_up(&self) -> bool {self.given_up}
+    /// get whether there's an action to undo
+    pub fn can_undo(&self) -> bool {!self.history.is_empty()}
+    /// get whether there's an undone action to redo
+    pub fn can_redo(&self) -> bool {!self.undone.is_empty()}
+}
+
+/// the rules a game is played by
+#[derive(Clone, Copy)]
+pub struct Rules {
+    /// whether the squares around the first checked square are also kept free of mines
+    pub safe_neighbours:bool,
+    /// whether the flags must match the mines exactly to win, rather than only revealing every other square
+    pub strict_flags:bool,
+    /// whether flagging a flagged square turns it into a question mark instead of removing the flag
+    pub marks:bool,
+    /// whether a lost game can be undone, in which case it doesn't count for the high scores
+    pub practice:bool,
+    /// whether the mines are placed so the field can be cleared from the first check without guessing
+    pub no_guess:bool,
+}
+impl Default for Rules {
+    fn default() -> Rules {
+        return Rules { safe_neighbours: true, strict_flags: false, marks: false, practice: false, no_guess: false };
+    }
+}
+
+/// something the player does to the square at (x,y)
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Action {
+    /// check a hidden square, or chord a visible one
+    CHECK(u8,u8),
+    /// add or remove a flag
+    FLAG(u8,u8),
+    /// reveal every unflagged square around a number that has as many flags around it as its danger
+    CHORD(u8,u8),
+}
+impl Action {
+    /// get the position of the square the action is for, as (x,y)
+    pub fn get_position(&self) -> (u8,u8) {
+        return match *self {
+            Action::CHECK(x_pos,y_pos) | Action::FLAG(x_pos,y_pos) | Action::CHORD(x_pos,y_pos) => (x_pos,y_pos),
+        }
+    }
+}
+
+/// what a hint found out
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hint {
+    /// the square at (x,y) is certainly safe
+    SAFE(u8,u8),
+    /// the square at (x,y) is certainly a mine
+    MINE(u8,u8),
+    /// nothing is certain, the player has to guess
+    GUESS,
+}
+
+/// what applying an action did
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    /// nothing changed, e.g. checking a square that's already visible
+    UNCHANGED,
+    /// this many squares were made visible
+    REVEALED(usize),
+    /// a flag was added
+    FLAGGED,
+    /// a flag was removed
+    UNFLAGGED,
+    /// a flag was turned into a question mark
+    MARKED,
+    /// a question mark was removed
+    UNMARKED,
+    /// the action won the game
+    WON,
+    /// the action hit a mine and lost the game
+    LOST,
+}
+
+/// reasons an action can't be applied
+#[derive(Debug, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum GameError {
+    /// the game is already won or lost
+    GAME_OVER,
+    /// the square at (x,y) isn't on the field
+    OUT_OF_BOUNDS(u8,u8),
+    /// a field can't be made for the difficulty, and why
+    INVALID_DIFFICULTY(String),
+    /// no action has changed the field (since the game was loaded)
+    NOTHING_TO_UNDO,
+    /// no action was undone since the last one was applied
+    NOTHING_TO_REDO,
+    /// no square has been checked yet
+    NOT_STARTED,
+    /// the field is too complex to work out how likely its squares are to be mines
+    TOO_COMPLEX,
+    /// none of this many layouts could be cleared without guessing
+    NO_GUESS_FIELD_NOT_FOUND(usize),
+}
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            GameError::GAME_OVER => write!(f, "the game is already over"),
+            GameError::OUT_OF_BOUNDS(x_pos,y_pos) => write!(f, "there is no square at ({},{})", x_pos, y_pos),
+            GameError::INVALID_DIFFICULTY(reason) => write!(f, "invalid difficulty: {}", reason),
+            GameError::NOTHING_TO_UNDO => write!(f, "there's nothing to undo"),
+            GameError::NOTHING_TO_REDO => write!(f, "there's nothing to redo"),
+            GameError::NOT_STARTED => write!(f, "no square has been checked yet"),
+            GameError::TOO_COMPLEX => write!(f, "the field is too complex to work out the chances of its squares being mines"),
+            GameError::NO_GUESS_FIELD_NOT_FOUND(n_attempts) => write!(f, "couldn't place the mines so the field can be cleared without guessing in {} tries, try checking another square or playing with fewer mines", n_attempts),
+        }
+    }
+}
+impl Error for GameError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// checks every hidden square that isn't a mine, returning what the last check did
+    fn check_safe_squares(game:&mut Game) -> Result<Outcome, GameError> {
+        let squares:Vec<(u8,u8)> = game.field().get_grid().iter().flatten()
+            .filter(|square| !square.is_mine() && State::HIDDEN.eq(square.get_state()))
+            .map(|square| square.get_position())
+            .collect();
+        let mut outcome = Ok(Outcome::UNCHANGED);
+        for (x_pos,y_pos) in squares {
+            //squares revealed by an earlier check are left as they are
+            if game.field().get_square_at(x_pos as isize, y_pos as isize).is_some_and(|square| State::HIDDEN.eq(square.get_state())) {
+                outcome = game.apply(Action::CHECK(x_pos, y_pos));
+            }
+        }
+        return outcome;
+    }
+
+    #[test]
+    fn first_check_is_safe() {
+        for seed in 0..50 {
+            let mut game = Game::new(Difficulty::BEGINNER, seed, Rules::default()).unwrap();
+            assert!(!game.field().is_populated());
+            assert!(matches!(game.apply(Action::CHECK(4,4)), Ok(Outcome::REVEALED(_) | Outcome::WON)));
+            assert!(game.field().is_populated());
+        }
+    }
+
+    #[test]
+    fn win_and_loss() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert_eq!(check_safe_squares(&mut game), Ok(Outcome::WON));
+        assert!(*game.status() == GameStatus::WON);
+        assert_eq!(game.apply(Action::CHECK(4,4)), Err(GameError::GAME_OVER));
+
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        let (x_pos,y_pos) = game.field().get_mines()[0];
+        assert_eq!(game.apply(Action::CHECK(x_pos, y_pos)), Ok(Outcome::LOST));
+        assert!(*game.status() == GameStatus::LOST);
+        //the mine that was hit is marked, and the rest are shown
+        let get_state = |(x_pos,y_pos):&(u8,u8)| *game.field().get_square_at(*x_pos as isize, *y_pos as isize).unwrap().get_state();
+        assert!(get_state(&(x_pos,y_pos)) == State::DETONATED);
+        assert!(game.field().get_mines()[1..].iter().all(|mine| get_state(mine) == State::VISIBLE));
+        assert_eq!(game.apply(Action::FLAG(0,0)), Err(GameError::GAME_OVER));
+    }
+
+    #[test]
+    fn loss_shows_wrong_flags() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        let (x_pos,y_pos) = game.field().get_grid().iter().flatten()
+            .find(|square| !square.is_mine() && State::HIDDEN.eq(square.get_state()))
+            .map(|square| square.get_position())
+            .unwrap();
+        game.apply(Action::FLAG(x_pos, y_pos)).unwrap();
+        let (mine_x,mine_y) = game.field().get_mines()[0];
+        assert_eq!(game.apply(Action::CHECK(mine_x, mine_y)), Ok(Outcome::LOST));
+        assert!(State::WRONG_FLAG.eq(game.field().get_square_at(x_pos as isize, y_pos as isize).unwrap().get_state()));
+    }
+
+    #[test]
+    fn strict_flags_need_every_mine_flagged() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules { strict_flags: true, ..Rules::default() }).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert!(matches!(check_safe_squares(&mut game), Ok(Outcome::REVEALED(_))));
+        assert!(*game.status() == GameStatus::PLAYING);
+
+        let mines = game.field().get_mines().clone();
+        let (last, rest) = mines.split_last().unwrap();
+        for (x_pos,y_pos) in rest {
+            assert_eq!(game.apply(Action::FLAG(*x_pos, *y_pos)), Ok(Outcome::FLAGGED));
+        }
+        assert_eq!(game.apply(Action::FLAG(last.0, last.1)), Ok(Outcome::WON));
+    }
+
+    #[test]
+    fn flags() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        assert_eq!(game.apply(Action::FLAG(0,0)), Ok(Outcome::FLAGGED));
+        assert_eq!(game.field().get_n_flags(), 1);
+        assert_eq!(game.apply(Action::FLAG(0,0)), Ok(Outcome::UNFLAGGED));
+        assert_eq!(game.field().get_n_flags(), 0);
+
+        //visible squares can't be flagged, or checked again
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert_eq!(game.apply(Action::FLAG(4,4)), Ok(Outcome::UNCHANGED));
+        assert_eq!(game.apply(Action::CHECK(4,4)), Ok(Outcome::UNCHANGED));
+    }
+
+    #[test]
+    fn marks() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules { marks: true, ..Rules::default() }).unwrap();
+        assert_eq!(game.apply(Action::FLAG(0,0)), Ok(Outcome::FLAGGED));
+        assert_eq!(game.apply(Action::FLAG(0,0)), Ok(Outcome::MARKED));
+        assert_eq!(game.field().get_n_flags(), 0);
+        assert!(State::MARKED.eq(game.field().get_square_at(0, 0).unwrap().get_state()));
+        assert_eq!(game.apply(Action::FLAG(0,0)), Ok(Outcome::UNMARKED));
+        assert!(State::HIDDEN.eq(game.field().get_square_at(0, 0).unwrap().get_state()));
+
+        //marked squares can still be checked
+        game.apply(Action::FLAG(4,4)).unwrap();
+        game.apply(Action::FLAG(4,4)).unwrap();
+        assert!(matches!(game.apply(Action::CHECK(4,4)), Ok(Outcome::REVEALED(_) | Outcome::WON)));
+    }
+
+    #[test]
+    fn only_changes_are_counted() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert_eq!(game.get_n_actions(), 1);
+        assert_eq!(game.apply(Action::CHECK(4,4)), Ok(Outcome::UNCHANGED));
+        assert_eq!(game.apply(Action::FLAG(4,4)), Ok(Outcome::UNCHANGED));
+        assert_eq!(game.apply(Action::CHECK(9,0)), Err(GameError::OUT_OF_BOUNDS(9,0)));
+        assert_eq!(game.get_n_actions(), 1);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        assert_eq!(game.undo(), Err(GameError::NOTHING_TO_UNDO));
+        game.apply(Action::CHECK(4,4)).unwrap();
+        let (x_pos,y_pos) = game.field().get_mines()[0];
+        game.apply(Action::FLAG(x_pos, y_pos)).unwrap();
+        let get_state = |game:&Game| *game.field().get_square_at(x_pos as isize, y_pos as isize).unwrap().get_state();
+        assert_eq!(game.get_n_actions(), 2);
+
+        game.undo().unwrap();
+        assert!(get_state(&game) == State::HIDDEN);
+        assert_eq!(game.get_n_actions(), 1);
+        assert!(game.can_redo());
+        game.redo().unwrap();
+        assert!(get_state(&game) == State::FLAGGED);
+        assert_eq!(game.get_n_actions(), 2);
+        assert_eq!(game.redo(), Err(GameError::NOTHING_TO_REDO));
+
+        //a new action can't be followed by the undone ones
+        game.undo().unwrap();
+        game.apply(Action::FLAG(0,0)).unwrap();
+        assert!(!game.can_redo());
+        game.undo().unwrap();
+        game.undo().unwrap();
+        assert!(!game.field().get_grid().iter().flatten().any(|square| State::VISIBLE.eq(square.get_state())));
+        assert_eq!(game.get_n_actions(), 0);
+        assert_eq!(game.undo(), Err(GameError::NOTHING_TO_UNDO));
+    }
+
+    #[test]
+    fn undo_after_a_loss_only_in_practice() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        let (x_pos,y_pos) = game.field().get_mines()[0];
+        game.apply(Action::CHECK(x_pos, y_pos)).unwrap();
+        assert_eq!(game.undo(), Err(GameError::GAME_OVER));
+        assert!(!game.is_practiced());
+
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules { practice: true, ..Rules::default() }).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert_eq!(game.apply(Action::CHECK(x_pos, y_pos)), Ok(Outcome::LOST));
+        game.undo().unwrap();
+        assert!(*game.status() == GameStatus::PLAYING);
+        assert!(State::HIDDEN.eq(game.field().get_square_at(x_pos as isize, y_pos as isize).unwrap().get_state()));
+        assert!(game.is_practiced());
+        //redoing the loss ends the game again
+        game.redo().unwrap();
+        assert!(*game.status() == GameStatus::LOST);
+
+        //but giving up can't be taken back
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules { practice: true, ..Rules::default() }).unwrap();
+        assert_eq!(game.give_up(), Err(GameError::NOT_STARTED));
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert_eq!(game.give_up(), Ok(()));
+        assert!(*game.status() == GameStatus::LOST && game.is_given_up());
+        assert_eq!(game.undo(), Err(GameError::GAME_OVER));
+    }
+
+    #[test]
+    fn restart_keeps_the_mines() {
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        game.restart();
+        assert!(!game.field().is_populated() && !game.is_practiced());
+
+        game.apply(Action::CHECK(4,4)).unwrap();
+        let mines = game.field().get_mines().clone();
+        game.apply(Action::CHECK(mines[0].0, mines[0].1)).unwrap();
+        game.restart();
+        assert!(*game.status() == GameStatus::PLAYING);
+        assert_eq!(game.field().get_mines(), &mines);
+        assert!(State::HIDDEN.eq(game.field().get_square_at(4, 4).unwrap().get_state()));
+        assert_eq!(game.get_n_actions(), 0);
+        assert!(!game.can_undo());
+        assert!(game.is_practiced());
+    }
+
+    #[test]
+    fn hints() {
+        let mut game = Game::new(Difficulty::BEGINNER, 0, Rules::default()).unwrap();
+        assert_eq!(game.hint(), Err(GameError::NOT_STARTED));
+
+        //a 1 between two hidden squares, with 2 mines to place among 4
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width: 5, height: 1, mines: 2 }, 0);
+        field.place_mines(&[(0,0), (3,0)]);
+        let mut game = Game::restore(field, Rules::default(), 0, 0, Duration::ZERO, false);
+        game.apply(Action::CHECK(1,0)).unwrap();
+        assert_eq!(game.hint(), Ok(Hint::GUESS));
+        //now both mines are known, so the last square is safe
+        game.apply(Action::CHECK(2,0)).unwrap();
+        assert_eq!(game.hint(), Ok(Hint::SAFE(4,0)));
+        assert_eq!(game.get_n_hints(), 2);
+        //the chances of mines count as a hint too
+        assert_eq!(game.probabilities().unwrap().get(4, 0), Some(0.0));
+        assert_eq!(game.get_n_hints(), 3);
+
+        game.restart();
+        assert_eq!(game.get_n_hints(), 0);
+
+        //a 2 with a mine on both sides, and no telling which of the last 2 squares is the third
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width: 5, height: 1, mines: 3 }, 0);
+        field.place_mines(&[(0,0), (2,0), (4,0)]);
+        let mut game = Game::restore(field, Rules::default(), 0, 0, Duration::ZERO, false);
+        game.apply(Action::CHECK(1,0)).unwrap();
+        assert_eq!(game.hint(), Ok(Hint::MINE(0,0)));
+        //flagged mines aren't pointed out again
+        game.apply(Action::FLAG(0,0)).unwrap();
+        assert_eq!(game.hint(), Ok(Hint::MINE(2,0)));
+    }
+
+    #[test]
+    fn no_guess_fields_can_be_cleared() {
+        assert!(matches!(
+            Game::new(Difficulty::CUSTOM { width: 5, height: 5, mines: 10 }, 0, Rules { no_guess: true, ..Rules::default() }),
+            Err(GameError::INVALID_DIFFICULTY(_))
+        ));
+
+        let mut game = Game::new(Difficulty::BEGINNER, 7, Rules { no_guess: true, ..Rules::default() }).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        assert!(solver::can_clear(game.field(), 4, 4));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(Game::new(Difficulty::CUSTOM { width: 2, height: 2, mines: 4 }, 0, Rules::default()), Err(GameError::INVALID_DIFFICULTY(_))));
+        let mut game = Game::new(Difficulty::BEGINNER, 42, Rules::default()).unwrap();
+        assert_eq!(game.apply(Action::CHECK(9,0)), Err(GameError::OUT_OF_BOUNDS(9,0)));
+        assert_eq!(game.apply(Action::FLAG(0,9)), Err(GameError::OUT_OF_BOUNDS(0,9)));
+    }
+}
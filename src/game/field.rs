@@ -1,137 +1,180 @@
-use rand::{Rng, prelude::thread_rng}; //rng
+use std::{collections::HashSet, fmt};
+
+use rand::{seq::SliceRandom, SeedableRng}; //rng
+use rand_chacha::ChaCha8Rng; //seedable rng that gives the same numbers on every platform
+
+use super::glyphs::GlyphSet;
 
 //DATA
-const HIDDEN: char = '-';
-const FLAGGED: char= 'f';
-const VISIBLE: &str= " 12345678";
-const MINE: char = '*';
+/// offsets of the 8 squares surrounding a square
+pub(crate) const NEIGHBOURS: [(isize,isize);8] = [(0,-1),(1,-1),(1,0),(1,1),(0,1),(-1,1),(-1,0),(-1,-1)];
 
 /**
- * handles the game field, a 26x26 grid of squares, each square is either a mine or not a mine, and has one of 3 states:
- * -visible
- * -hidden
- * -flagged
+ * handles the game field, a width x height grid of squares (up to 100x100, see Difficulty),
+ * each square is either a mine or not a mine, and has one of the states in State:
+ * hidden, flagged or question marked while playing, visible once checked,
+ * and detonated or wrongly flagged once the game is lost
  */
-
+#[derive(Clone)]
 pub struct Field {
     grid: Vec<Vec<Square>>,
     difficulty: Difficulty,
     mines: Vec<(u8,u8)>,
     n_mines:usize,
     n_flags:usize,
+    populated:bool,
+    seed:u64,
+    /// the square the mines were placed around, the same seed only places the same mines for the same first check,
+    /// None until then (or if it isn't known, e.g. in an older save)
+    first_check:Option<(u8,u8)>,
 }
 impl Field {
     /// creates new empty field
     pub fn new() -> Field {
         let field:Field = Field { 
             grid: Vec::new(),
-            difficulty: Difficulty::BEGINNER, //default value, changed in populate
+            difficulty: Difficulty::BEGINNER, //default value, changed in initialize
             mines: Vec::new(),
             n_mines: 0,
             n_flags: 0,
+            populated: false,
+            seed: 0,
+            first_check: None,
         };
 
         return field
     }
-    /// populates the field as a FIELD_DIMENSIONS x FIELD_DIMENSIONS grid with num_mines mines
-    pub fn populate(&mut self, difficulty:Difficulty) {
-        //update difficulty
+    /// lays out a width x height grid of hidden squares with no mines in it,
+    /// mines are only placed once the first square is checked (see populate), using the given seed
+    pub fn initialize(&mut self, difficulty:Difficulty, seed:u64) {
+        //update difficulty and seed
         self.difficulty = difficulty;
+        self.seed = seed;
         //DATA
-        let dimensions = self.difficulty.get_dimensions();
-        let mut rng = thread_rng();
-
-        //populate field with sqaures
-        for row in 0..dimensions {
-            //create new row
-            let mut new_row: Vec<Square> = Vec::new();
-
-            //fill it with squares
-            for col in 0..dimensions {
-                //DATA
-                let mut curr_square:Square = Square::new(col,row,rng.gen_bool(self.difficulty.p_is_mine()));
-
-                //if it's a mine, update mine count and increase the danger of each adjactent square
-                if curr_square.is_mine() {
-                    self.n_mines += 1; //update mine count
-                    //add mine position to mines
-                    self.mines.push((col,row));
-                    
-                    //update north square
-                    if let Some(square) = self.get_square_at_mut(col as isize, row as isize - 1) {
-                        square.danger += 1;
-                    }
-                    //update west square
-                    if col > 0 {//have to do it different because the row is still being built
-                        if let Some(square) = new_row.get_mut(col as usize -1) {
-                            square.danger += 1;
-                        }
-                    }
-                    //update northwest square
-                    if let Some(square) = self.get_square_at_mut(col as isize - 1, row as isize - 1) {
-                        square.danger += 1;
-                    }
-                    //update northeast square
-                    if let Some(square) = self.get_square_at_mut(col as isize + 1, row as isize - 1) {
-                        square.danger += 1;
-                    }
-                } 
-                //otherwise set danger to the number of adjacent mines
-                else {
-                    //check north square
-                    if let Some(square) = self.get_square_at(col as isize, row as isize - 1) {
-                        if square.is_mine() {
-                            curr_square.danger += 1;
-                        }
-                    }
-                    //check west square
-                    if col > 0 { //have to do it different because the row is still being built
-                        if let Some(square) = new_row.get_mut(col as usize -1) {
-                            if square.is_mine() {
-                                curr_square.danger += 1;
-                            }
-                        }
-                    }
-                    //check northwest square
-                    if let Some(square) = self.get_square_at(col as isize - 1, row as isize - 1) {
-                        if square.is_mine() {
-                            curr_square.danger += 1;
-                        }
-                    }
-                    //check northeast square
-                    if let Some(square) = self.get_square_at(col as isize + 1, row as isize - 1) {
-                        if square.is_mine() {
-                            curr_square.danger += 1;
-                        }
-                    }
+        let (width,height) = self.difficulty.get_dimensions();
+
+        //fill field with empty sqaures
+        self.grid = (0..height).map(|row| (0..width).map(|col| Square::new(col,row,false)).collect()).collect();
+        self.mines.clear();
+        self.n_mines = self.difficulty.get_n_mines();
+        self.n_flags = 0;
+        self.populated = false;
+        self.first_check = None;
+    }
+    /// places mines in the field, the square at (x_pos,y_pos) (and its 8 neighbours if safe_neighbours is set) 
+    /// is guaranteed to not be a mine, so that the first check is always safe,
+    /// the same seed (and first check) always places the same mines
+    pub fn populate(&mut self, x_pos:u8, y_pos:u8, safe_neighbours:bool) {
+        self.populate_with(x_pos, y_pos, safe_neighbours, &mut ChaCha8Rng::seed_from_u64(self.seed));
+    }
+    /// places mines in the field like populate, but drawing them from the given rng instead of the seed
+    pub fn populate_with(&mut self, x_pos:u8, y_pos:u8, safe_neighbours:bool, rng:&mut ChaCha8Rng) {
+        //every square that is allowed to hold a mine
+        let mut candidates:Vec<(u8,u8)> = self.get_mine_candidates(x_pos, y_pos, safe_neighbours);
+        //on very dense boards there may not be enough room to keep the neighbours safe too, so only keep the square itself safe
+        if candidates.len() < self.difficulty.get_n_mines() {
+            candidates = self.get_mine_candidates(x_pos, y_pos, false);
+        }
+
+        //place exactly as many mines as the difficulty calls for, each on a distinct square
+        let mines:Vec<(u8,u8)> = candidates.choose_multiple(rng, self.difficulty.get_n_mines().min(candidates.len())).cloned().collect();
+        self.place_mines(&mines);
+        self.first_check = Some((x_pos,y_pos));
+    }
+    /// places mines at exactly the given positions (e.g. those of a saved game), 
+    /// and sets the danger of every square to the number of adjacent mines
+    pub fn place_mines(&mut self, mines:&[(u8,u8)]) {
+        self.mines = mines.to_vec();
+        self.n_mines = self.mines.len(); //update mine count
+        for (col,row) in self.mines.clone() {
+            if let Some(square) = self.get_square_at_mut(col as isize, row as isize) {
+                square.is_mine = true;
+            }
+        }
+
+        //now that every mine is placed, set the danger of every square to the number of adjacent mines
+        for (x_pos,y_pos) in self.mines.clone() {
+            for (dx,dy) in NEIGHBOURS {
+                if let Some(square) = self.get_square_at_mut(x_pos as isize + dx, y_pos as isize + dy) {
+                    square.danger += 1;
                 }
-                
-                //make new square and add it to row
-                new_row.push(curr_square);
             }
-            
-            //add row to field
-            self.grid.push(new_row);
         }
+
+        self.populated = true;
     }
 
-    ///makes all the mines visible
-    pub fn show_mines(&mut self) {
-        let mines = self.mines.clone();
-        for (x_pos,y_pos) in mines.iter() {
-            if let Some(sqr) = self.get_square_at_mut(*x_pos as isize, *y_pos as isize) {
-                if State::HIDDEN.eq(sqr.get_state()) {
-                    sqr.set_state(State::VISIBLE);
+    /// gets the positions of every square that may hold a mine, that is every square except the one at (x_pos,y_pos),
+    /// and its 8 neighbours if safe_neighbours is set
+    fn get_mine_candidates(&self, x_pos:u8, y_pos:u8, safe_neighbours:bool) -> Vec<(u8,u8)> {
+        return self.grid.iter().flatten()
+            .map(|square| square.get_position())
+            .filter(|(col,row)| {
+                let dx = (*col as isize - x_pos as isize).abs();
+                let dy = (*row as isize - y_pos as isize).abs();
+                if safe_neighbours {dx > 1 || dy > 1} else {dx != 0 || dy != 0}
+            })
+            .collect();
+    }
+
+    /// gets the status of the game played on this field:
+    /// - it's lost once a mine is visible
+    /// - it's won once every square that isn't a mine is visible,
+    ///   if strict_flags is set every mine (and only the mines) must also be flagged
+    pub fn get_status(&self, strict_flags:bool) -> GameStatus {
+        //nothing can be won or lost before the mines are placed
+        if !self.populated {
+            return GameStatus::PLAYING;
+        }
+
+        //check the mines
+        let mut flags_match_mines = true;
+        for (x_pos,y_pos) in self.get_mines().iter() {
+            if let Some(square) = self.get_square_at(*x_pos as isize, *y_pos as isize) {
+                match square.get_state() {
+                    State::VISIBLE | State::DETONATED => return GameStatus::LOST,
+                    State::HIDDEN | State::MARKED => flags_match_mines = false,
+                    State::FLAGGED | State::WRONG_FLAG => {},
                 }
             }
         }
+
+        //check every other square
+        let mut all_safe_squares_visible = true;
+        for square in self.grid.iter().flatten().filter(|square| !square.is_mine()) {
+            match square.get_state() {
+                State::VISIBLE | State::DETONATED => {},
+                State::HIDDEN | State::MARKED => all_safe_squares_visible = false,
+                State::FLAGGED | State::WRONG_FLAG => {all_safe_squares_visible = false; flags_match_mines = false},
+            }
+        }
+
+        if all_safe_squares_visible && (flags_match_mines || !strict_flags) {
+            return GameStatus::WON;
+        }
+        return GameStatus::PLAYING;
+    }
+
+    ///shows what went wrong once the game is lost, flagged mines stay flagged but:
+    /// - the mines that were checked are detonated
+    /// - the hidden mines are made visible
+    /// - the flags that aren't on mines are wrong
+    pub fn show_mines(&mut self) {
+        for square in self.grid.iter_mut().flatten() {
+            match (square.get_state(), square.is_mine()) {
+                (State::VISIBLE, true) => square.set_state(State::DETONATED),
+                (State::HIDDEN | State::MARKED, true) => square.set_state(State::VISIBLE),
+                (State::FLAGGED, false) => square.set_state(State::WRONG_FLAG),
+                _ => {},
+            }
+        }
     }
 
     ///makes the whole field visible
     pub fn show_field(&mut self) {
         for row in self.grid.iter_mut() {
             for square in row.iter_mut() {
-                if State::HIDDEN.eq(square.get_state()) {
+                if square.get_state().is_hidden() {
                     square.set_state(State::VISIBLE);
                 }
             }
@@ -139,54 +182,134 @@ impl Field {
     }
 
 
-    ///(used in a loop iterating over backlog) gets the square at the given position 
-    ///and checks all squares adjacent to it, making them visible and adding them 
-    ///to the backlog if their danger is 0 (they aren't bordering a mine)
-    pub fn check_and_update_states_of_adjacent_squares(&mut self, x_pos:isize,y_pos:isize) -> Vec<(isize,isize)> {
-        let mut backlog:Vec<(isize,isize)> = Vec::new();
+    /// makes the hidden (or question marked) square at the given position visible,
+    /// if it's neither a mine nor bordering one, all surrounding non-mines that aren't bordering mines are made visible aswell
+    pub fn reveal(&mut self, x_pos:isize, y_pos:isize) {
+        //make it visible
+        let square = match self.get_square_at_mut(x_pos, y_pos) {
+            Some(square) if square.get_state().is_hidden() => square,
+            _ => return,
+        };
+        square.set_state(State::VISIBLE);
 
-        //N
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos, y_pos-1) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos,y_pos-1))}
-            tmp_sqr.set_state(State::VISIBLE);
-        }
-        //NE
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos+1, y_pos-1) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos+1, y_pos-1))}
-            tmp_sqr.set_state(State::VISIBLE);
+        //if bordering a mine (or a mine itself), stop here
+        if square.is_mine() || square.get_danger() > 0 {
+            return;
         }
-        //E
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos+1, y_pos) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos+1, y_pos))}
-            tmp_sqr.set_state(State::VISIBLE);
+
+        //data
+        let mut backlog:HashSet<(isize,isize)> = [(x_pos,y_pos)].iter().cloned().collect();
+        let mut curr_queue:HashSet<(isize,isize)>;
+        loop {
+            curr_queue = backlog;
+            backlog = HashSet::new();
+
+            //make all squares bordering each square in curr_queue visible, and add all that have 0 danger to backlog
+            for (x_pos,y_pos) in curr_queue.iter() {
+                backlog.extend(self.check_and_update_states_of_adjacent_squares(*x_pos, *y_pos).iter());
+            }
+
+            //if queue is empty, exit
+            if backlog.is_empty() {
+                break;
+            }
         }
-        //SE
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos+1, y_pos+1) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos+1, y_pos+1))}
-            tmp_sqr.set_state(State::VISIBLE);
+    }
+
+    /// "chords" the visible square at the given position:
+    /// if the number of flags around it matches its danger, every hidden square around it is revealed,
+    /// which hits a mine if any of those flags is wrong
+    /// 
+    /// returns whether the flags matched the danger
+    pub fn chord(&mut self, x_pos:isize, y_pos:isize) -> bool {
+        //only visible numbers can be chorded
+        let danger = match self.get_square_at(x_pos, y_pos) {
+            Some(square) if State::VISIBLE.eq(square.get_state()) && !square.is_mine() => square.get_danger(),
+            _ => return false,
+        };
+
+        //count the flags around it
+        let n_flags = NEIGHBOURS.iter()
+            .filter_map(|(dx,dy)| self.get_square_at(x_pos+dx, y_pos+dy))
+            .filter(|square| State::FLAGGED.eq(square.get_state()))
+            .count();
+        if n_flags != danger {
+            return false;
         }
-        //S
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos, y_pos+1) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos, y_pos+1))}
-            tmp_sqr.set_state(State::VISIBLE);
+
+        //reveal everything else around it
+        for (dx,dy) in NEIGHBOURS {
+            self.reveal(x_pos+dx, y_pos+dy);
         }
-        //SW
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos-1, y_pos+1) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos-1, y_pos+1))}
-            tmp_sqr.set_state(State::VISIBLE);
+        return true;
+    }
+
+    ///(used in a loop iterating over backlog) gets the square at the given position 
+    ///and checks all squares adjacent to it, making the hidden ones visible and adding them 
+    ///to the backlog if their danger is 0 (they aren't bordering a mine)
+    pub fn check_and_update_states_of_adjacent_squares(&mut self, x_pos:isize,y_pos:isize) -> Vec<(isize,isize)> {
+        let mut backlog:Vec<(isize,isize)> = Vec::new();
+
+        //flagged squares are left alone, the player thinks they're mines
+        for (dx,dy) in NEIGHBOURS {
+            if let Some(tmp_sqr) = self.get_square_at_mut(x_pos+dx, y_pos+dy) {
+                if tmp_sqr.get_state().is_hidden() {
+                    if tmp_sqr.get_danger() == 0 {backlog.push((x_pos+dx, y_pos+dy))}
+                    tmp_sqr.set_state(State::VISIBLE);
+                }
+            }
         }
-        //W
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos-1, y_pos) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos-1, y_pos))}
-            tmp_sqr.set_state(State::VISIBLE);
+
+        backlog
+    }
+
+    /// gets the 3BV of the field (the least number of checks needed to clear it without chording) as (total, solved),
+    /// where solved is how much of it has been made visible so far
+    /// - every opening (group of touching squares with a danger of 0, and the numbers around them) counts as 1
+    /// - every other square that isn't a mine counts as 1
+    pub fn get_3bv(&self) -> (usize,usize) {
+        //DATA
+        let mut total:usize = 0;
+        let mut solved:usize = 0;
+        let mut in_opening:HashSet<(isize,isize)> = HashSet::new();
+
+        //count the openings, and mark every square in them
+        for square in self.grid.iter().flatten() {
+            let position = (square.x_pos as isize, square.y_pos as isize);
+            if square.is_mine() || square.get_danger() > 0 || in_opening.contains(&position) {
+                continue;
+            }
+
+            //flood the opening
+            let mut is_solved = false;
+            let mut backlog:Vec<(isize,isize)> = vec![position];
+            in_opening.insert(position);
+            while let Some((x_pos,y_pos)) = backlog.pop() {
+                is_solved |= State::VISIBLE.eq(self.get_square_at(x_pos, y_pos).map_or(&State::HIDDEN, |sqr| sqr.get_state()));
+                for (dx,dy) in NEIGHBOURS {
+                    if let Some(tmp_sqr) = self.get_square_at(x_pos+dx, y_pos+dy) {
+                        //only squares with a danger of 0 spread the opening further
+                        if in_opening.insert((x_pos+dx, y_pos+dy)) && tmp_sqr.get_danger() == 0 {
+                            backlog.push((x_pos+dx, y_pos+dy));
+                        }
+                    }
+                }
+            }
+
+            total += 1;
+            if is_solved {solved += 1}
         }
-        //NW
-        if let Some(tmp_sqr) = self.get_square_at_mut(x_pos-1, y_pos-1) {
-            if tmp_sqr.get_danger() == 0 && State::HIDDEN.eq(tmp_sqr.get_state()) {backlog.push((x_pos-1, y_pos-1))}
-            tmp_sqr.set_state(State::VISIBLE);
+
+        //count every other square that isn't a mine
+        for square in self.grid.iter().flatten() {
+            if square.is_mine() || in_opening.contains(&(square.x_pos as isize, square.y_pos as isize)) {
+                continue;
+            }
+            total += 1;
+            if State::VISIBLE.eq(square.get_state()) {solved += 1}
         }
 
-        backlog
+        return (total, solved);
     }
 
     //getters
@@ -196,6 +319,20 @@ impl Field {
     pub fn get_n_mines(&self) -> usize {self.n_mines}
     /// get n_flags
     pub fn get_n_flags(&self) -> usize {self.n_flags}
+    /// get the number of visible squares
+    pub fn get_n_visible(&self) -> usize {
+        return self.grid.iter().flatten().filter(|square| State::VISIBLE.eq(square.get_state())).count();
+    }
+    /// get the difficulty
+    pub fn get_difficulty(&self) -> &Difficulty {&self.difficulty}
+    /// get the seed the mines are placed with
+    pub fn get_seed(&self) -> u64 {self.seed}
+    /// get the square the mines were placed around, if it's known
+    pub fn get_first_check(&self) -> Option<(u8,u8)> {self.first_check}
+    /// set the square the mines were placed around, for mines placed with place_mines
+    pub fn set_first_check(&mut self, first_check:Option<(u8,u8)>) {self.first_check = first_check}
+    /// get whether mines have been placed yet
+    pub fn is_populated(&self) -> bool {self.populated}
     /// get mines
     pub fn get_mines(&self) -> &Vec<(u8,u8)> {&self.mines}
     /// get a reference to the square at the given x and y coordinate
@@ -220,8 +357,8 @@ impl Field {
         }
         return None;
     }
-    /// get field dimensions from difficulty
-    pub fn get_dimensions(&self) -> u8 {self.difficulty.get_dimensions()}
+    /// get field dimensions from difficulty, as (width,height)
+    pub fn get_dimensions(&self) -> (u8,u8) {self.difficulty.get_dimensions()}
 
     //incrementers and decrementers
     ///increments n_flags
@@ -230,7 +367,12 @@ impl Field {
     pub fn decrement_n_flags(&mut self) {self.n_flags -= 1}
 }
 
+impl Default for Field {
+    fn default() -> Field {Field::new()}
+}
+
 /// a single square on a grid
+#[derive(Clone)]
 pub struct Square {
     x_pos:u8,
     y_pos:u8,
@@ -243,12 +385,15 @@ impl Square {
     pub fn new(x_pos:u8,y_pos:u8,is_mine:bool) -> Square {
         return Self { is_mine, state: State::HIDDEN, x_pos, y_pos, danger: 0}
     }
-    /// return icon associated with the squares state
-    pub fn get_icon(&self) -> char {
+    /// return icon associated with the squares state, from the given glyph set
+    pub fn get_icon(&self, glyphs:&GlyphSet) -> char {
         return match self.state {
-            State::HIDDEN => HIDDEN,
-            State::VISIBLE => {if self.is_mine {MINE} else {VISIBLE.chars().nth(self.danger).unwrap()}},
-            State::FLAGGED => FLAGGED,
+            State::HIDDEN => glyphs.hidden,
+            State::MARKED => glyphs.marked,
+            State::VISIBLE => {if self.is_mine {glyphs.mine} else {glyphs.numbers[self.danger]}},
+            State::FLAGGED => glyphs.flag,
+            State::DETONATED => glyphs.detonated,
+            State::WRONG_FLAG => glyphs.wrong_flag,
         }
     }
     //getters and setters
@@ -266,34 +411,267 @@ impl Square {
     pub fn set_state(&mut self, state:State) {self.state = state}
 }
 
-#[derive(PartialEq)]
+#[allow(non_camel_case_types)]
+#[derive(PartialEq, Clone, Copy, Debug)]
 pub enum State {
     HIDDEN,
     VISIBLE,
     FLAGGED,
+    /// a question mark, the player isn't sure whether it's a mine, it's still hidden otherwise
+    MARKED,
+    /// a mine that was checked, losing the game
+    DETONATED,
+    /// a flag on a square that isn't a mine, once the game is lost
+    WRONG_FLAG,
+}
+
+impl State {
+    /// returns whether the square is hidden, with or without a question mark
+    pub fn is_hidden(&self) -> bool {
+        return matches!(self, State::HIDDEN | State::MARKED);
+    }
 }
 
-//#[derive(Clone, Copy)]
+/// the status of a game
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum GameStatus {
+    PLAYING,
+    WON,
+    LOST,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Difficulty {
     BEGINNER,
     INTERMEDIATE,
     ADVANCED,
+    /// the classic expert layout, 30 columns wide and 16 rows tall
+    EXPERT,
+    CUSTOM {width:u8, height:u8, mines:usize},
 }
 impl Difficulty {
-    /// dimensions of field based on difficulty 
-    pub fn get_dimensions(&self) -> u8 {
+    /// the widest field, columns are labelled a-z, then aa, ab, and so on
+    pub const MAX_WIDTH:u8 = 100;
+    /// the tallest field, row numbers are printed with at most 2 digits
+    pub const MAX_HEIGHT:u8 = 100;
+
+    /// gets the preset difficulty with the given name (or first letter of the name)
+    pub fn from_name(name:&str) -> Option<Difficulty> {
+        return match name.to_ascii_lowercase().as_str() {
+            "b" | "beginner" => Some(Difficulty::BEGINNER),
+            "i" | "intermediate" => Some(Difficulty::INTERMEDIATE),
+            "a" | "advanced" => Some(Difficulty::ADVANCED),
+            "e" | "expert" => Some(Difficulty::EXPERT),
+            _ => None,
+        }
+    }
+    /// name of the difficulty
+    pub fn get_name(&self) -> &'static str {
         return match *self {
-            Difficulty::BEGINNER => 9,
-            Difficulty::INTERMEDIATE => 16,
-            Difficulty::ADVANCED => 24,
+            Difficulty::BEGINNER => "beginner",
+            Difficulty::INTERMEDIATE => "intermediate",
+            Difficulty::ADVANCED => "advanced",
+            Difficulty::EXPERT => "expert",
+            Difficulty::CUSTOM { .. } => "custom",
         }
     }
-    /// probability a square is a mine based on difficulty
-    pub fn p_is_mine(&self) -> f64 {
+    /// dimensions of field based on difficulty, as (width,height)
+    pub fn get_dimensions(&self) -> (u8,u8) {
         return match *self {
-            Difficulty::BEGINNER => 0.12, // 10/81
-            Difficulty::INTERMEDIATE => 0.15, // 40/256
-            Difficulty::ADVANCED => 0.17, // 99/576
+            Difficulty::BEGINNER => (9,9),
+            Difficulty::INTERMEDIATE => (16,16),
+            Difficulty::ADVANCED => (24,24),
+            Difficulty::EXPERT => (30,16),
+            Difficulty::CUSTOM { width, height, .. } => (width,height),
+        }
+    }
+    /// number of mines on the field based on difficulty
+    pub fn get_n_mines(&self) -> usize {
+        return match *self {
+            Difficulty::BEGINNER => 10, // 10/81
+            Difficulty::INTERMEDIATE => 40, // 40/256
+            Difficulty::ADVANCED => 99, // 99/576
+            Difficulty::EXPERT => 99, // 99/480
+            Difficulty::CUSTOM { mines, .. } => mines,
+        }
+    }
+    /// checks that a field can be made for this difficulty
+    /// 
+    /// Errors:
+    /// the field is empty, too wide, or too tall
+    /// there isn't at least one square that isn't a mine
+    pub fn validate(&self) -> Result<(), String> {
+        let (width,height) = self.get_dimensions();
+        let n_squares = width as usize * height as usize;
+
+        if width == 0 || height == 0 {
+            return Err(String::from("the field must be at least 1x1"));
+        }
+        if width > Difficulty::MAX_WIDTH {
+            return Err(format!("the field can be at most {} columns wide", Difficulty::MAX_WIDTH));
+        }
+        if height > Difficulty::MAX_HEIGHT {
+            return Err(format!("the field can be at most {} rows tall", Difficulty::MAX_HEIGHT));
+        }
+        if self.get_n_mines() >= n_squares {
+            return Err(format!("a {}x{} field must have fewer than {} mines", width, height, n_squares));
+        }
+        return Ok(());
+    }
+}
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (width,height) = self.get_dimensions();
+        return write!(f, "{} ({}x{}, {} mines)", self.get_name(), width, height, self.get_n_mines());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// makes a beginner field with mines at exactly the given positions, every square hidden
+    fn get_field(mines:&[(u8,u8)]) -> Field {
+        let mut field = Field::new();
+        field.initialize(Difficulty::BEGINNER, 0);
+        for (x_pos,y_pos) in mines {
+            field.get_square_at_mut(*x_pos as isize, *y_pos as isize).unwrap().is_mine = true;
+            for (dx,dy) in NEIGHBOURS {
+                if let Some(square) = field.get_square_at_mut(*x_pos as isize + dx, *y_pos as isize + dy) {
+                    square.danger += 1;
+                }
+            }
+        }
+        field.mines = mines.to_vec();
+        field.n_mines = mines.len();
+        field.populated = true;
+        return field;
+    }
+
+    /// makes every square that isn't a mine visible
+    fn reveal_safe_squares(field:&mut Field) {
+        field.grid.iter_mut().flatten().filter(|square| !square.is_mine()).for_each(|square| square.set_state(State::VISIBLE));
+    }
+
+    #[test]
+    fn first_check_is_safe() {
+        for seed in 0..50 {
+            for safe_neighbours in [true, false] {
+                let mut field = Field::new();
+                field.initialize(Difficulty::BEGINNER, seed);
+                assert!(!field.is_populated());
+                assert!(field.get_mines().is_empty());
+
+                field.populate(4, 4, safe_neighbours);
+                assert!(field.is_populated());
+                let is_safe = |(x_pos,y_pos):&(u8,u8)| if safe_neighbours {x_pos.abs_diff(4) > 1 || y_pos.abs_diff(4) > 1} else {(*x_pos,*y_pos) != (4,4)};
+                assert!(field.get_mines().iter().all(is_safe), "seed {}: {:?}", seed, field.get_mines());
+            }
         }
     }
+
+    #[test]
+    fn same_seed_same_mines() {
+        let get_mines = |seed:u64, x_pos:u8, y_pos:u8| {
+            let mut field = Field::new();
+            field.initialize(Difficulty::INTERMEDIATE, seed);
+            field.populate(x_pos, y_pos, true);
+            return field.get_mines().clone();
+        };
+        assert_eq!(get_mines(42, 2, 3), get_mines(42, 2, 3));
+        assert_ne!(get_mines(42, 2, 3), get_mines(43, 2, 3));
+    }
+
+    #[test]
+    fn danger_counts_the_mines_around() {
+        let mut field = Field::new();
+        field.initialize(Difficulty::INTERMEDIATE, 0);
+        field.populate(0, 0, true);
+        for square in field.get_grid().iter().flatten() {
+            let (x_pos,y_pos) = square.get_position();
+            let n_mines = NEIGHBOURS.iter().filter(|(dx,dy)| field.get_square_at(x_pos as isize + dx, y_pos as isize + dy).is_some_and(|square| square.is_mine())).count();
+            assert_eq!(square.get_danger(), n_mines, "({},{})", x_pos, y_pos);
+        }
+    }
+
+    #[test]
+    fn fields_can_be_rectangular() {
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width: 5, height: 3, mines: 4 }, 0);
+        assert_eq!(field.get_dimensions(), (5,3));
+        assert_eq!(field.get_grid().len(), 3);
+        assert!(field.get_grid().iter().all(|row| row.len() == 5));
+        field.populate(4, 2, false);
+        assert_eq!(field.get_mines().len(), 4);
+    }
+
+    #[test]
+    fn difficulties_are_validated() {
+        assert!(Difficulty::CUSTOM { width: 5, height: 3, mines: 14 }.validate().is_ok());
+        assert!(Difficulty::CUSTOM { width: 5, height: 3, mines: 15 }.validate().is_err());
+        assert!(Difficulty::CUSTOM { width: 0, height: 3, mines: 0 }.validate().is_err());
+        assert!(Difficulty::CUSTOM { width: 101, height: 3, mines: 1 }.validate().is_err());
+        assert_eq!(Difficulty::EXPERT.get_dimensions(), (30,16));
+        assert!(Difficulty::EXPERT.validate().is_ok());
+    }
+
+    #[test]
+    fn nothing_is_won_before_the_mines_are_placed() {
+        let mut field = Field::new();
+        field.initialize(Difficulty::BEGINNER, 0);
+        assert!(field.get_status(false) == GameStatus::PLAYING);
+    }
+
+    #[test]
+    fn won_once_every_safe_square_is_visible() {
+        let mut field = get_field(&[(0,0), (8,8)]);
+        assert!(field.get_status(false) == GameStatus::PLAYING);
+        reveal_safe_squares(&mut field);
+        assert!(field.get_status(false) == GameStatus::WON);
+    }
+
+    #[test]
+    fn strict_flags_need_every_mine_flagged() {
+        let mut field = get_field(&[(0,0), (8,8)]);
+        reveal_safe_squares(&mut field);
+        assert!(field.get_status(true) == GameStatus::PLAYING);
+        field.get_square_at_mut(0, 0).unwrap().set_state(State::FLAGGED);
+        assert!(field.get_status(true) == GameStatus::PLAYING);
+        field.get_square_at_mut(8, 8).unwrap().set_state(State::FLAGGED);
+        assert!(field.get_status(true) == GameStatus::WON);
+
+        //flagging every mine isn't enough without revealing the rest
+        let mut field = get_field(&[(0,0), (8,8)]);
+        field.get_square_at_mut(0, 0).unwrap().set_state(State::FLAGGED);
+        field.get_square_at_mut(8, 8).unwrap().set_state(State::FLAGGED);
+        assert!(field.get_status(true) == GameStatus::PLAYING);
+    }
+
+    #[test]
+    fn chording() {
+        let mut field = get_field(&[(0,0), (8,8)]);
+        field.reveal(1, 1);
+        //not enough flags yet, and hidden squares can't be chorded
+        assert!(!field.chord(1, 1));
+        assert!(!field.chord(2, 2));
+        field.get_square_at_mut(0, 0).unwrap().set_state(State::FLAGGED);
+        assert!(field.chord(1, 1));
+        //the 0 it revealed opened up everything else
+        assert!(field.get_status(false) == GameStatus::WON);
+
+        //a wrong flag hits the mine
+        let mut field = get_field(&[(0,0), (8,8)]);
+        field.reveal(1, 1);
+        field.get_square_at_mut(1, 0).unwrap().set_state(State::FLAGGED);
+        assert!(field.chord(1, 1));
+        assert!(field.get_status(false) == GameStatus::LOST);
+    }
+
+    #[test]
+    fn lost_once_a_mine_is_visible() {
+        let mut field = get_field(&[(0,0), (8,8)]);
+        field.get_square_at_mut(8, 8).unwrap().set_state(State::VISIBLE);
+        assert!(field.get_status(false) == GameStatus::LOST);
+        assert!(field.get_status(true) == GameStatus::LOST);
+    }
 }
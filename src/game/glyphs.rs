@@ -0,0 +1,68 @@
+//DATA
+/// every glyph set, the first one is the default
+pub const GLYPH_SETS: [GlyphSet; 3] = [GlyphSet::ASCII, GlyphSet::UNICODE, GlyphSet::NERD];
+
+/// the characters squares (and the flag counter) are drawn with, every one of them is a single column wide
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GlyphSet {
+    pub name: &'static str,
+    pub hidden: char,
+    pub flag: char,
+    /// a question mark
+    pub marked: char,
+    pub mine: char,
+    /// the mine that was checked, losing the game
+    pub detonated: char,
+    /// a flag on a square that isn't a mine, once the game is lost
+    pub wrong_flag: char,
+    /// visible squares that aren't mines, by the number of mines around them
+    pub numbers: [char; 9],
+    /// drawn in place of the square a hint points out when there are no colours to highlight it with
+    pub highlight: char,
+}
+impl GlyphSet {
+    /// plain ASCII, works everywhere
+    pub const ASCII: GlyphSet = GlyphSet {
+        name: "ascii",
+        hidden: '-',
+        flag: 'f',
+        marked: '?',
+        mine: '*',
+        detonated: 'X',
+        wrong_flag: 'x',
+        numbers: [' ', '1', '2', '3', '4', '5', '6', '7', '8'],
+        highlight: '@',
+    };
+    /// box drawing and symbol characters, needs a font with unicode symbols (most have them)
+    pub const UNICODE: GlyphSet = GlyphSet {
+        name: "unicode",
+        hidden: '■',
+        flag: '⚑',
+        marked: '?',
+        mine: '✱',
+        detonated: '✸',
+        wrong_flag: '✗',
+        numbers: ['·', '1', '2', '3', '4', '5', '6', '7', '8'],
+        highlight: '◎',
+    };
+    /// icons from a Nerd Font (https://www.nerdfonts.com), they show up as boxes in any other font
+    pub const NERD: GlyphSet = GlyphSet {
+        name: "nerd",
+        hidden: '\u{f0c8}',
+        flag: '\u{f024}',
+        marked: '\u{f128}',
+        mine: '\u{f1e2}',
+        detonated: '\u{f06d}',
+        wrong_flag: '\u{f00d}',
+        numbers: [' ', '1', '2', '3', '4', '5', '6', '7', '8'],
+        highlight: '\u{f05b}',
+    };
+
+    /// gets the glyph set with the given name, None if there's none
+    pub fn from_name(name:&str) -> Option<GlyphSet> {
+        return GLYPH_SETS.into_iter().find(|glyphs| glyphs.name.eq_ignore_ascii_case(name));
+    }
+}
+impl Default for GlyphSet {
+    fn default() -> GlyphSet {GLYPH_SETS[0]}
+}
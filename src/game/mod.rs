@@ -1,150 +1,326 @@
-use std::{error::Error, collections::HashSet};
+use std::{error::Error, fs};
 
 mod cli_utils;
 pub mod config;
-mod field;
+pub mod engine;
+pub mod field;
+pub mod glyphs;
+pub mod parser;
+pub mod probabilities;
+pub mod save;
+pub mod scores;
+pub mod solver;
+pub mod theme;
+pub mod tui;
 
-/// run the program
-pub fn run(config: &mut config::Config) -> Result<(), Box<dyn Error>> {
+use engine::{Game, GameError, Hint};
+use parser::Command;
+use probabilities::Probabilities;
+use theme::Theme;
+
+//DATA
+/// what hints and the chances of mines say before the first check, as there's nothing to work from yet
+const FIRST_CHECK_IS_SAFE: &str = "the first check is never a mine, so any square is safe";
+
+/// how playing a game ended
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Ending {
+    /// the game was won or lost
+    GAME_OVER,
+    /// the player quit before the game was over (it stays autosaved)
+    QUIT,
+}
+
+/// run the program, a command line frontend over the game engine,
+/// until the game is over or the player quits
+pub fn run(config: &mut config::Config) -> Result<Ending, Box<dyn Error>> {
     //DATA
-    let column_letter_range = (0u8..config.field.get_dimensions()).fold(String::new(), |mut acc, i| {acc.push((i+97) as char); acc});
-    let row_number_range = 0..(config.field.get_dimensions() as usize);
+    // what the last commands did, printed under the board, starting with how to play
+    let mut messages:Vec<String> = vec![cli_utils::get_command_instructions()];
+    // the square the last hint pointed out, highlighted until the next commands
+    let mut highlighted:Option<(u8,u8)> = None;
+
+    // a finished game (e.g. a loaded one) can't be played any further
+    if *config.game.status() != field::GameStatus::PLAYING {
+        println!("this game is already over");
+        cli_utils::print_board(config.game.field(), &config.theme, None);
+        cli_utils::print_result(&config.game);
+        return Ok(Ending::GAME_OVER);
+    }
+
     //for every round
     loop {
-        let mut game_over:bool = true;
-        //DATA
-        let mines:Vec<(u8,u8)> = (*config).field.get_mines().clone();
-        let command;
-
         // print board state
-        cli_utils::reset_screen();
-        cli_utils::print_game_state(&config.field);
+        if !config.no_clear {
+            cli_utils::reset_screen();
+        }
+        cli_utils::print_game_state(&config.game, &config.theme, highlighted.take());
+        for message in messages.drain(..) {
+            println!("{}", message);
+        }
         
-        // allow user to add a flag, or check the state of a square
+        // allow user to enter commands
         //input loop
-        command = loop { match cli_utils::get_string_from_user_input("Enter command: ") {
-            Ok(s) => { //Verify input
-                //check prefix
-                let prefix;
-                match s.to_ascii_lowercase().chars().nth(0) {
-                    Some('c') => prefix = 'c',
-                    Some('f') => prefix = 'f',
-                    _ => {eprintln!("invalid command prefix");continue;},
-                }
-                
-                //check column letter
-                let column_letter;
-                match s.to_ascii_lowercase().chars().nth(1) {
-                    Some(c) => {
-                        match column_letter_range.find(c) {
-                            Some(index) => column_letter = index,
-                            None => {eprintln!("invalid column letter");continue;}
-                        }
-                    },
-                    _ => {eprintln!("invalid column letter");continue;},
-                }
-
-                //check row number
-                let row_number;
-                match s[2..].parse::<usize>() {
-                    Ok(row) => {
-                        if row_number_range.contains(&row) {
-                            row_number = row;
-                        }
-                        else { 
-                            eprintln!("invalid row number");continue;
-                        }
-                    },
-                    _ => {eprintln!("invalid row number");continue;},
-                }
-
-                break (prefix,column_letter,row_number);
+        let commands = loop { match cli_utils::get_string_from_user_input("Enter command: ") {
+            Ok(s) => match parser::parse_line(&s, config.game.field().get_dimensions()) {
+                Ok(commands) if commands.is_empty() => continue,
+                Ok(commands) => break commands,
+                Err(e) => eprintln!("{}",e),
+            },
+            Err(e) if cli_utils::is_end_of_input(e.as_ref()) => {
+                print_autosave_hint();
+                return Ok(Ending::QUIT);
             },
             Err(e) => eprintln!("{}",e),
         }};
 
-        // clear screen
-        cli_utils::reset_screen();
-
-        // handle command
-        if let Some(square) = config.field.get_square_at_mut(command.1 as isize, command.2 as isize) {
-            //what prefix is being used?
-            if command.0 == 'f' { //toggle flag
-                if field::State::FLAGGED.eq(square.get_state()) {
-                    square.set_state(field::State::HIDDEN); 
-                    config.field.decrement_n_flags();
-                }
-                else {
-                    square.set_state(field::State::FLAGGED);
-                    config.field.increment_n_flags();
-                }
+        // handle every command, until the game is won or lost
+        for command in commands {
+            if *config.game.status() != field::GameStatus::PLAYING {
+                break;
             }
-            else if command.0 == 'c' {
-                //if square is not hidden, just skip this command input
-                if field::State::HIDDEN.ne(square.get_state()) {
-                    continue;
-                }
+            if command == Command::QUIT {
+                print_autosave_hint();
+                return Ok(Ending::QUIT);
+            }
+            if let Err(e) = handle_command(config, command, &mut messages, &mut highlighted) {
+                messages.push(e.to_string());
+            }
+        }
 
-                //make it visible
-                square.set_state(field::State::VISIBLE);
-
-                //end game if checked square is a mine 
-                if square.is_mine() { //game over
-                    println!("you hit a mine, you lose");
-                    // go through every mine and make it visible
-                    config.field.show_mines();
-                    //print updated board
-                    cli_utils::print_board(&config.field);
-                    break;
-                }
-                // if bordering a mine, continue
-                if square.get_danger() > 0 {
-                    continue;
-                }
-                // make all surrounding non-mines that aren't bordering mines visible aswell
-                else {
-                    //data
-                    let mut backlog:HashSet<(isize,isize)> = [(square.get_position().0 as isize, square.get_position().1 as isize)].iter().cloned().collect();
-                    let mut curr_queue:HashSet<(isize,isize)>;
-                    loop {
-                        curr_queue = backlog;
-                        backlog = HashSet::new();
-
-                        //make all squares bordering each square in curr_queue visible, and add all that have 0 danger to backlog
-                        for (x_pos,y_pos) in curr_queue.iter() {
-                            //cli_utils::reset_screen();
-                            backlog.extend(config.field.check_and_update_states_of_adjacent_squares(*x_pos, *y_pos).iter());
-                            //cli_utils::print_game_state(&config.field);
-                        }
-
-                        //if queue is empty, exit
-                        if backlog.len() <= 0 {
-                            break;
-                        }
-                    }
-                }
+        // end the game once it's won or lost
+        if *config.game.status() != field::GameStatus::PLAYING {
+            if !config.no_clear {
+                cli_utils::reset_screen();
+            }
+            end_game(&config.game, &config.theme);
+            //practice games can take back the move that lost them
+            if config.game.rules().practice && offer_undo(&mut config.game) {
+                continue;
             }
+            break;
         }
+    }
 
-        // if no unflagged mines are remaining, then end game
-        //specifically, this checks all the mines and if any are hidden (not flagged) it will continue (i.e. not end the game)
-        for (x_pos,y_pos) in mines.iter() {
-            if let Some(square) = config.field.get_square_at_mut(*x_pos as isize, *y_pos as isize) {
-                if !field::State::FLAGGED.eq(square.get_state()) {
-                    game_over = false;
-                    break;
+    //return to main
+    Ok(Ending::GAME_OVER)
+}
+
+/// applies a command (other than quit) to the game,
+/// and adds any message for the player to messages (and the square a hint points out to highlighted),
+/// the player is asked to confirm anything that would throw away a game in progress
+///
+/// Errors:
+/// the command can't be applied to the game
+/// the game can't be saved
+fn handle_command(config: &mut config::Config, command: Command, messages: &mut Vec<String>, highlighted: &mut Option<(u8,u8)>) -> Result<(), Box<dyn Error>> {
+    //DATA
+    let game = &mut config.game;
+
+    match command {
+        Command::HINT => {
+            let (message, square) = get_hint(game)?;
+            messages.push(message);
+            *highlighted = square;
+        },
+        Command::PROBS => match get_probabilities(game) {
+            Ok(probabilities) => messages.push(cli_utils::get_probabilities_board(game.field(), &config.theme, &probabilities)),
+            Err(message) => messages.push(message),
+        },
+        Command::HELP => messages.push(cli_utils::get_command_instructions()),
+        Command::QUIT => {},
+        Command::SAVE(path) => {
+            //save to the given path, or the default one
+            let path = match path {
+                Some(path) => path,
+                None => save::get_default_save_path().ok_or("couldn't find a directory to save to, give a path instead")?,
+            };
+            save::save_game(game, &path).map_err(|e| format!("couldn't save game: {}", e))?;
+            messages.push(format!("game saved to {}, resume it with --load {}", path.display(), path.display()));
+        },
+        command => {
+            if let Some(question) = get_confirmation(game, &command) {
+                if !cli_utils::confirm(&format!("{}: ", question)) {
+                    return Ok(());
                 }
             }
+            apply_command(game, command)?;
+        },
+    }
+    return Ok(());
+}
+
+/// asks the game for a hint and autosaves it (as hints are counted), for both frontends,
+/// returning a message saying what it found, and the square it points out if there is one
+///
+/// Errors:
+/// the game is already over
+fn get_hint(game: &mut Game) -> Result<(String, Option<(u8,u8)>), GameError> {
+    //DATA
+    let result = game.hint();
+    let get_label = |x_pos:u8, y_pos:u8| format!("{}{}", cli_utils::get_column_label(x_pos), y_pos);
+
+    autosave(game);
+    return match result {
+        Ok(Hint::SAFE(x_pos,y_pos)) => match game.field().get_square_at(x_pos as isize, y_pos as isize).map(|square| *square.get_state()) {
+            Some(field::State::FLAGGED) => Ok((format!("hint: {} is safe, so its flag is wrong", get_label(x_pos, y_pos)), Some((x_pos,y_pos)))),
+            _ => Ok((format!("hint: {} is safe", get_label(x_pos, y_pos)), Some((x_pos,y_pos)))),
+        },
+        Ok(Hint::MINE(x_pos,y_pos)) => Ok((format!("hint: {} is a mine", get_label(x_pos, y_pos)), Some((x_pos,y_pos)))),
+        Ok(Hint::GUESS) => Ok((String::from("hint: nothing is certain from what's visible, you'll have to guess"), None)),
+        Err(GameError::NOT_STARTED) => Ok((format!("hint: {}", FIRST_CHECK_IS_SAFE), None)),
+        Err(e) => Err(e),
+    };
+}
+
+/// works out how likely every hidden square is to be a mine and autosaves the game (as it counts as a hint), for both frontends
+///
+/// Errors:
+/// the chances can't be worked out, with a message saying why
+fn get_probabilities(game: &mut Game) -> Result<Probabilities, String> {
+    let result = game.probabilities();
+    autosave(game);
+    return match result {
+        Ok(probabilities) => Ok(probabilities),
+        Err(GameError::NOT_STARTED) => Err(String::from(FIRST_CHECK_IS_SAFE)),
+        Err(e) => Err(e.to_string()),
+    };
+}
+
+/// gets the question to ask the player before applying a command that would throw away the game in progress,
+/// None if it can be applied straight away
+fn get_confirmation(game: &Game, command: &Command) -> Option<&'static str> {
+    //nothing is lost before the first check
+    if !game.field().is_populated() {
+        return None;
+    }
+    return match command {
+        Command::RESTART => Some("start this game over on the same field? it won't count for the high scores (y/N)"),
+        Command::NEW(_) => Some("give up this game and start a new one? (y/N)"),
+        Command::REVEAL_ALL => Some("give up and reveal the mines? (y/N)"),
+        _ => None,
+    };
+}
+
+/// applies a command that changes the game to it and autosaves it, for both frontends,
+/// help, quit and save are left to them as they don't change the game
+///
+/// Errors:
+/// the command can't be applied to the game
+fn apply_command(game: &mut Game, command: Command) -> Result<(), GameError> {
+    let result = match command {
+        Command::ACTION(action) => game.apply(action).map(|_| ()),
+        Command::UNDO => game.undo(),
+        Command::REDO => game.redo(),
+        Command::RESTART => {game.restart(); Ok(())},
+        Command::NEW(difficulty) => {
+            let difficulty = difficulty.unwrap_or(*game.field().get_difficulty());
+            Game::new(difficulty, rand::random(), *game.rules()).map(|new_game| *game = new_game)
+        },
+        Command::REVEAL_ALL => game.give_up(),
+        Command::HINT | Command::PROBS | Command::HELP | Command::QUIT | Command::SAVE(_) => return Ok(()),
+    };
+    autosave(game);
+    return result;
+}
+
+/// prints the final board and stats of a won or lost game, and records the score of a won one
+fn end_game(game: &Game, theme: &Theme) {
+    match game.status() {
+        field::GameStatus::LOST if game.is_given_up() => println!("you gave up, here's where the mines were"),
+        field::GameStatus::LOST => println!("you hit a mine, you lose"),
+        field::GameStatus::WON => println!("You win, congradulations!"),
+        field::GameStatus::PLAYING => return,
+    }
+    //print updated board
+    cli_utils::print_board(game.field(), theme, None);
+    cli_utils::print_result(game);
+
+    //remember the win, unless a lost game was undone (or restarted) to get it
+    if *game.status() == field::GameStatus::WON && game.is_practiced() {
+        println!("this game was practiced (a mine was hit and undone, or it was restarted), so it doesn't count for the high scores");
+    } else if *game.status() == field::GameStatus::WON {
+        if let Err(e) = record_win(game) {
+            eprintln!("couldn't save score: {}",e);
         }
-        if game_over {
-            println!("You win, congradulations!");
-            // make the whole field visible
-            config.field.show_field();
-            cli_utils::print_board(&config.field);
-            break;
+    }
+}
+
+/// asks the player whether to take back the move that lost the game, and does so if they want to,
+/// returns whether it was taken back
+fn offer_undo(game: &mut Game) -> bool {
+    if *game.status() != field::GameStatus::LOST || game.is_given_up() {
+        return false;
+    }
+    return match cli_utils::get_string_from_user_input("undo the move that hit the mine? (y/N): ") {
+        Ok(s) if s.eq_ignore_ascii_case("y") => apply_command(game, Command::UNDO).is_ok(),
+        _ => false,
+    };
+}
+
+/// asks the player whether they want to play another game
+pub fn offer_another_game() -> bool {
+    println!();
+    return cli_utils::confirm("play another game? (y/N): ");
+}
+
+/// tells the player how to resume the game, if it was autosaved
+fn print_autosave_hint() {
+    if let Some(path) = save::get_autosave_path().filter(|path| path.exists()) {
+        println!("game autosaved, resume it with --load {}", path.display());
+    }
+}
+
+/// saves the game to the autosave file, so that it can be resumed if the program is closed,
+/// or removes that file once the game is over
+fn autosave(game: &Game) {
+    //DATA
+    let path = match save::get_autosave_path() {
+        Some(path) => path,
+        None => return,
+    };
+
+    if *game.status() == field::GameStatus::PLAYING {
+        if let Err(e) = save::save_game(game, &path) {
+            eprintln!("couldn't autosave game: {}",e);
         }
     }
+    else if path.exists() {
+        let _ = fs::remove_file(&path);
+    }
+}
 
-    //return to main
-    Ok(())
+/// asks for the players name, saves the score of the won game, and shows where it ranks
+fn record_win(game: &Game) -> Result<(), Box<dyn Error>> {
+    //DATA
+    let difficulty = *game.field().get_difficulty();
+    let name = cli_utils::get_string_from_user_input("Enter your name for the high scores: ")?;
+    let score = scores::Score::new(difficulty, game.elapsed(), game.field().get_seed(), game.field().get_first_check(), &name);
+
+    scores::save_score(&score)?;
+
+    let all_scores = scores::load_scores()?;
+    println!();
+    cli_utils::print_scores(difficulty, &scores::get_top_scores(&all_scores, difficulty), Some(&score));
+    return Ok(());
+}
+
+/// prints the high scores of every difficulty that has any
+pub fn print_scores() -> Result<(), Box<dyn Error>> {
+    //DATA
+    let all_scores = scores::load_scores()?;
+    let mut difficulties:Vec<field::Difficulty> = Vec::new();
+
+    //the presets come first, then every custom field that has been won on
+    for difficulty in [field::Difficulty::BEGINNER, field::Difficulty::INTERMEDIATE, field::Difficulty::ADVANCED, field::Difficulty::EXPERT].into_iter().chain(all_scores.iter().map(|score| score.difficulty)) {
+        if !difficulties.contains(&difficulty) {
+            difficulties.push(difficulty);
+        }
+    }
+
+    for difficulty in difficulties {
+        cli_utils::print_scores(difficulty, &scores::get_top_scores(&all_scores, difficulty), None);
+    }
+    return Ok(());
 }
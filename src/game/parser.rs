@@ -0,0 +1,335 @@
+use std::{error::Error, fmt, iter::Peekable, path::PathBuf, vec::IntoIter};
+
+use super::cli_utils;
+use super::engine::Action;
+use super::field::Difficulty;
+
+/*
+ * parses the commands typed in by the player, a line can hold any number of them:
+ * - an action: a prefix (c check, f flag, d chord) followed by a position,
+ *   either column letters and a row number in any order (a3, 3a, ab12) or a column and row number (0,3),
+ *   in any case and with any whitespace in between (fa1, F A 1, f 1a)
+ * - undo, redo
+ * - hint, probs, help, quit, restart, reveal-all
+ * - new, followed by an optional difficulty
+ * - save, followed by an optional path, which takes up the rest of the line
+ */
+
+/// something the player asks for
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq, Debug)]
+pub enum Command {
+    ACTION(Action),
+    UNDO,
+    REDO,
+    /// point out a square that's certainly safe, or certainly a mine
+    HINT,
+    /// show how likely every hidden square is to be a mine
+    PROBS,
+    /// save the game, to the given path or the default one
+    SAVE(Option<PathBuf>),
+    /// print the commands
+    HELP,
+    /// stop playing, the game stays autosaved
+    QUIT,
+    /// start over on the same field
+    RESTART,
+    /// start a new game, on the given difficulty or the same one as now
+    NEW(Option<Difficulty>),
+    /// give up, showing where the mines are
+    REVEAL_ALL,
+}
+
+/// why a line couldn't be parsed
+#[allow(non_camel_case_types)]
+#[derive(Clone, PartialEq, Debug)]
+pub enum ParseError {
+    /// a character that can't be part of any command
+    UNEXPECTED_CHARACTER(char),
+    /// a word that isn't a command
+    UNKNOWN_COMMAND(String),
+    /// the action (given as typed so far) isn't followed by a full position
+    MISSING_POSITION(String),
+    /// the action and column (given as typed so far) aren't followed by a row number
+    MISSING_ROW(String),
+    /// a number that's part of the position of an action isn't followed by what should come next
+    INCOMPLETE_POSITION(String),
+    /// a column that isn't on the field, and how many columns there are
+    INVALID_COLUMN(String, u8),
+    /// a row that isn't on the field, and how many rows there are
+    INVALID_ROW(String, u8),
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            ParseError::UNEXPECTED_CHARACTER(c) => write!(f, "'{}' can't be part of a command, only letters, numbers, commas and spaces can", c),
+            ParseError::UNKNOWN_COMMAND(word) => write!(f, "'{}' isn't a command, expected c (check), f (flag) or d (chord) followed by a square, or one of undo, redo, hint, probs, save, help, quit, restart, new or reveal-all", word),
+            ParseError::MISSING_POSITION(action) => write!(f, "'{}' needs a square, like {}a3, {}3a or {}0,3", action, action, action, action),
+            ParseError::MISSING_ROW(action) => write!(f, "'{}' needs a row number after its column, like {}3", action, action),
+            ParseError::INCOMPLETE_POSITION(position) => write!(f, "'{}' isn't a full square, expected column letters after the row number (like 3a) or a row number after the comma (like 0,3)", position),
+            ParseError::INVALID_COLUMN(column, width) => write!(f, "there's no column '{}', columns go from a to {} (or 0 to {})", column, cli_utils::get_column_label(width.saturating_sub(1)), width.saturating_sub(1)),
+            ParseError::INVALID_ROW(row, height) => write!(f, "there's no row {}, rows go from 0 to {}", row, height.saturating_sub(1)),
+        }
+    }
+}
+impl Error for ParseError {}
+
+/// a piece of a line
+#[derive(Clone, PartialEq, Debug)]
+enum Token {
+    WORD(String),
+    NUMBER(String),
+    COMMA,
+}
+/// a token, with where it starts in the line and whether whitespace comes right before it
+#[derive(Clone, Debug)]
+struct Spanned {
+    token: Token,
+    start: usize,
+    spaced: bool,
+}
+
+/// parses a line of input into the commands in it, for a field of the given (width,height),
+/// an empty line has no commands
+///
+/// Errors:
+/// anything in the line isn't a command, or is an action for a square that isn't on the field,
+/// in which case none of the commands in it are returned
+pub fn parse_line(line:&str, dimensions:(u8,u8)) -> Result<Vec<Command>, ParseError> {
+    //DATA
+    let mut tokens = tokenize(line)?.into_iter().peekable();
+    let mut commands:Vec<Command> = Vec::new();
+
+    while let Some(spanned) = tokens.next() {
+        let word = match spanned.token {
+            Token::WORD(word) => word,
+            Token::NUMBER(text) => return Err(ParseError::UNKNOWN_COMMAND(text)),
+            Token::COMMA => return Err(ParseError::UNKNOWN_COMMAND(String::from(","))),
+        };
+
+        let command = match word.to_ascii_lowercase().as_str() {
+            "undo" => Command::UNDO,
+            "redo" => Command::REDO,
+            "hint" => Command::HINT,
+            "probs" => Command::PROBS,
+            "help" => Command::HELP,
+            "quit" | "exit" => Command::QUIT,
+            "restart" => Command::RESTART,
+            "reveal-all" => Command::REVEAL_ALL,
+            //the difficulty is optional
+            "new" => match tokens.peek().map(|spanned| &spanned.token) {
+                Some(Token::WORD(name)) if Difficulty::from_name(name).is_some() => {
+                    let difficulty = Difficulty::from_name(name);
+                    tokens.next();
+                    Command::NEW(difficulty)
+                },
+                _ => Command::NEW(None),
+            },
+            //the path is the rest of the line
+            "save" => {
+                let path = line[spanned.start + word.len()..].trim();
+                commands.push(Command::SAVE(if path.is_empty() {None} else {Some(PathBuf::from(path))}));
+                break;
+            },
+            _ => parse_action(&word, &mut tokens, dimensions)?,
+        };
+        commands.push(command);
+    }
+
+    return Ok(commands);
+}
+
+/// parses an action starting with the given word, taking the rest of its position from the tokens
+fn parse_action(word:&str, tokens:&mut Peekable<IntoIter<Spanned>>, dimensions:(u8,u8)) -> Result<Command, ParseError> {
+    //DATA
+    let (width,height) = dimensions;
+    let mut chars = word.chars();
+    let prefix = chars.next().unwrap_or_default();
+    let column_after_prefix = chars.as_str();
+
+    //check prefix
+    let action: fn(u8,u8) -> Action = match prefix.to_ascii_lowercase() {
+        'c' => Action::CHECK,
+        'f' => Action::FLAG,
+        'd' => Action::CHORD,
+        _ => return Err(ParseError::UNKNOWN_COMMAND(String::from(word))),
+    };
+
+    //get the column and row as typed
+    let (column, row):(String, String) = if !column_after_prefix.is_empty() {
+        //fa3
+        match tokens.next() {
+            Some(Spanned { token: Token::NUMBER(row), .. }) => (String::from(column_after_prefix), row),
+            _ => return Err(ParseError::MISSING_ROW(String::from(word))),
+        }
+    } else {
+        match tokens.next().map(|spanned| spanned.token) {
+            //f a3
+            Some(Token::WORD(column)) => match tokens.next() {
+                Some(Spanned { token: Token::NUMBER(row), .. }) => (column, row),
+                _ => return Err(ParseError::MISSING_ROW(format!("{} {}", word, column))),
+            },
+            //f 3a, or f 0,3
+            Some(Token::NUMBER(number)) => match tokens.peek().cloned() {
+                Some(Spanned { token: Token::WORD(column), spaced: false, .. }) => {
+                    tokens.next();
+                    (column, number)
+                },
+                Some(Spanned { token: Token::COMMA, .. }) => {
+                    tokens.next();
+                    match tokens.next() {
+                        Some(Spanned { token: Token::NUMBER(row), .. }) => (number, row),
+                        _ => return Err(ParseError::INCOMPLETE_POSITION(format!("{},", number))),
+                    }
+                },
+                _ => return Err(ParseError::INCOMPLETE_POSITION(number)),
+            },
+            _ => return Err(ParseError::MISSING_POSITION(String::from(word))),
+        }
+    };
+
+    //check column, given as letters or a number
+    let x_pos = match column.parse::<usize>() {
+        Ok(x_pos) => Some(x_pos),
+        Err(_) => cli_utils::parse_column_label(&column),
+    };
+    let x_pos = match x_pos {
+        Some(x_pos) if x_pos < width as usize => x_pos,
+        _ => return Err(ParseError::INVALID_COLUMN(column.to_ascii_lowercase(), width)),
+    };
+
+    //check row number
+    let y_pos = match row.parse::<usize>() {
+        Ok(y_pos) if y_pos < height as usize => y_pos,
+        _ => return Err(ParseError::INVALID_ROW(row, height)),
+    };
+
+    return Ok(Command::ACTION(action(x_pos as u8, y_pos as u8)));
+}
+
+/// splits a line into words (runs of letters and dashes), numbers (runs of digits) and commas,
+/// up to a save command, as the path after it can have any characters
+///
+/// Errors:
+/// the line has any other character that isn't whitespace
+fn tokenize(line:&str) -> Result<Vec<Spanned>, ParseError> {
+    //DATA
+    let mut tokens:Vec<Spanned> = Vec::new();
+    let mut chars = line.char_indices().peekable();
+    let mut spaced = true;
+
+    while let Some((start, c)) = chars.next() {
+        //runs of letters or digits make up a single token
+        let mut take_run = |is_part:fn(&char) -> bool| {
+            let mut run = String::from(c);
+            while let Some((_, next)) = chars.next_if(|(_, next)| is_part(next)) {
+                run.push(next);
+            }
+            return run;
+        };
+        let token = match c {
+            c if c.is_whitespace() => {spaced = true; continue;},
+            c if c.is_ascii_alphabetic() => Token::WORD(take_run(|c| c.is_ascii_alphabetic() || *c == '-')),
+            c if c.is_ascii_digit() => Token::NUMBER(take_run(char::is_ascii_digit)),
+            ',' => Token::COMMA,
+            c => return Err(ParseError::UNEXPECTED_CHARACTER(c)),
+        };
+        let is_save = matches!(&token, Token::WORD(word) if word.eq_ignore_ascii_case("save"));
+        tokens.push(Spanned { token, start, spaced });
+        spaced = false;
+        if is_save {
+            break;
+        }
+    }
+
+    return Ok(tokens);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands() {
+        let check = |x_pos, y_pos| Command::ACTION(Action::CHECK(x_pos, y_pos));
+        let flag = |x_pos, y_pos| Command::ACTION(Action::FLAG(x_pos, y_pos));
+        let chord = |x_pos, y_pos| Command::ACTION(Action::CHORD(x_pos, y_pos));
+        let cases:Vec<(&str, Vec<Command>)> = vec![
+            ("", vec![]),
+            ("   ", vec![]),
+            //positions, in every form
+            ("fa1", vec![flag(0,1)]),
+            ("f a 3", vec![flag(0,3)]),
+            ("F A 3", vec![flag(0,3)]),
+            ("f a3", vec![flag(0,3)]),
+            ("f3a", vec![flag(0,3)]),
+            ("f 3a", vec![flag(0,3)]),
+            ("c 3a", vec![check(0,3)]),
+            ("c0,3", vec![check(0,3)]),
+            ("c 4 , 2", vec![check(4,2)]),
+            ("di8", vec![chord(8,8)]),
+            //several on a line, with or without spaces between them
+            ("ca1 fb2 dc3", vec![check(0,1), flag(1,2), chord(2,3)]),
+            ("ca1fb2", vec![check(0,1), flag(1,2)]),
+            ("c 0,1 undo redo", vec![check(0,1), Command::UNDO, Command::REDO]),
+            //the rest
+            ("hint probs help", vec![Command::HINT, Command::PROBS, Command::HELP]),
+            ("QUIT", vec![Command::QUIT]),
+            ("exit", vec![Command::QUIT]),
+            ("restart reveal-all", vec![Command::RESTART, Command::REVEAL_ALL]),
+            ("new", vec![Command::NEW(None)]),
+            ("new expert", vec![Command::NEW(Some(Difficulty::EXPERT))]),
+            ("new i undo", vec![Command::NEW(Some(Difficulty::INTERMEDIATE)), Command::UNDO]),
+            ("new undo", vec![Command::NEW(None), Command::UNDO]),
+            //the path is the rest of the line, whatever's in it
+            ("save", vec![Command::SAVE(None)]),
+            ("save  ", vec![Command::SAVE(None)]),
+            ("save ~/games/a game (1).txt", vec![Command::SAVE(Some(PathBuf::from("~/games/a game (1).txt")))]),
+            ("ca1 save game", vec![check(0,1), Command::SAVE(Some(PathBuf::from("game")))]),
+        ];
+
+        for (line, commands) in cases {
+            assert_eq!(parse_line(line, (9,9)), Ok(commands), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn parses_long_column_labels() {
+        assert_eq!(parse_line("c ab12", (30,16)), Ok(vec![Command::ACTION(Action::CHECK(27,12))]));
+        assert_eq!(parse_line("c 12ab", (30,16)), Ok(vec![Command::ACTION(Action::CHECK(27,12))]));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let cases:Vec<(&str, ParseError)> = vec![
+            ("c a1!", ParseError::UNEXPECTED_CHARACTER('!')),
+            ("c a1; f b2", ParseError::UNEXPECTED_CHARACTER(';')),
+            ("3a", ParseError::UNKNOWN_COMMAND(String::from("3"))),
+            (", 3", ParseError::UNKNOWN_COMMAND(String::from(","))),
+            ("x a1", ParseError::UNKNOWN_COMMAND(String::from("x"))),
+            ("undo nope", ParseError::UNKNOWN_COMMAND(String::from("nope"))),
+            ("f", ParseError::MISSING_POSITION(String::from("f"))),
+            ("f undo", ParseError::MISSING_ROW(String::from("f undo"))),
+            ("fa", ParseError::MISSING_ROW(String::from("fa"))),
+            ("fc,3", ParseError::MISSING_ROW(String::from("fc"))),
+            ("f a", ParseError::MISSING_ROW(String::from("f a"))),
+            ("f 3", ParseError::INCOMPLETE_POSITION(String::from("3"))),
+            ("f 3 a", ParseError::INCOMPLETE_POSITION(String::from("3"))),
+            ("f 3,", ParseError::INCOMPLETE_POSITION(String::from("3,"))),
+            ("c J1", ParseError::INVALID_COLUMN(String::from("j"), 9)),
+            ("c 9,1", ParseError::INVALID_COLUMN(String::from("9"), 9)),
+            ("c a9", ParseError::INVALID_ROW(String::from("9"), 9)),
+            ("c 0,10", ParseError::INVALID_ROW(String::from("10"), 9)),
+        ];
+
+        for (line, error) in cases {
+            assert_eq!(parse_line(line, (9,9)), Err(error), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn errors_explain_themselves() {
+        assert_eq!(ParseError::INVALID_COLUMN(String::from("j"), 9).to_string(), "there's no column 'j', columns go from a to i (or 0 to 8)");
+        assert_eq!(ParseError::INVALID_ROW(String::from("9"), 9).to_string(), "there's no row 9, rows go from 0 to 8");
+    }
+}
@@ -0,0 +1,651 @@
+use std::collections::HashSet;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::solver::{self, View};
+
+/*
+ * works out how likely every hidden square is to be a mine, from what the player can see:
+ * the squares around revealed numbers (the frontier) are split into groups that share no numbers,
+ * the mine layouts of every group are counted exactly, and combined with the number of ways
+ * the rest of the mines fit in the squares away from any number (the interior),
+ * so that the total mine count is taken into account.
+ * if there are too many layouts to count, they're sampled instead, and the probabilities are estimates
+ */
+
+//DATA
+/// the most squares tried while counting layouts exactly, before sampling them instead
+const MAX_STEPS: usize = 2_000_000;
+/// the number of layouts sampled, and how many of the first ones are thrown away
+const N_SAMPLES: usize = 20_000;
+const BURN_IN: usize = 2_000;
+/// the most squares laid out again at once while sampling
+const BLOCK_SIZE: usize = 20;
+/// the most layouts of every group looked through for one to start sampling from
+const MAX_START_LAYOUTS: usize = 1_000;
+/// the seed layouts are sampled from, so the estimates of a board are always the same
+const SAMPLE_SEED: u64 = 0;
+
+/// how likely every hidden square of a view is to be a mine
+#[derive(Clone, PartialEq, Debug)]
+pub struct Probabilities {
+    width: u8,
+    height: u8,
+    /// row by row, from the top left corner, None for revealed squares
+    cells: Vec<Option<f64>>,
+    /// whether every layout was counted, rather than sampled
+    exact: bool,
+}
+impl Probabilities {
+    //getters
+    /// get the dimensions as (width,height)
+    pub fn get_dimensions(&self) -> (u8,u8) {(self.width,self.height)}
+    /// get the probability of the square at the given position being a mine, None if it's revealed or not on the field
+    pub fn get(&self, x_pos:u8, y_pos:u8) -> Option<f64> {
+        if x_pos >= self.width || y_pos >= self.height {
+            return None;
+        }
+        return self.cells[y_pos as usize * self.width as usize + x_pos as usize];
+    }
+    /// get whether the probabilities are exact, rather than estimated by sampling
+    pub fn is_exact(&self) -> bool {self.exact}
+}
+
+/// the frontier of a view: its unknown squares around numbers, and the numbers' constraints on them by index
+struct Frontier {
+    squares: Vec<(u8,u8)>,
+    /// the squares of every constraint, and how many of them are mines
+    constraints: Vec<(Vec<usize>, usize)>,
+    /// the constraints every square is part of
+    square_constraints: Vec<Vec<usize>>,
+}
+
+/// the mine layouts of a group of frontier squares that share no numbers with the rest
+struct Group {
+    squares: Vec<usize>,
+    /// the number of layouts with every number of mines
+    counts: Vec<f64>,
+    /// for every number of mines, how many of those layouts have each of the squares a mine
+    tallies: Vec<Vec<f64>>,
+}
+
+/// a backtracking search for the mine layouts of some of the frontier's squares that agree with every constraint
+struct Search<'a> {
+    frontier: &'a Frontier,
+    /// whether every square is a mine, only meaningful for those already assigned
+    mines: Vec<bool>,
+    /// for every constraint, the mines assigned to it so far, and how many of its squares are left to assign
+    placed: Vec<usize>,
+    unassigned: Vec<usize>,
+    /// how many more squares can be tried, once it runs out the search gives up
+    steps_left: usize,
+}
+impl<'a> Search<'a> {
+    /// sets up a search over the given squares of the frontier, keeping the others as they are in mines
+    fn new(frontier:&'a Frontier, mines:Vec<bool>, squares:&[usize], steps_left:usize) -> Search<'a> {
+        //DATA
+        let mut is_searched = vec![false; frontier.squares.len()];
+        squares.iter().for_each(|square| is_searched[*square] = true);
+
+        let placed = frontier.constraints.iter().map(|(squares,_)| squares.iter().filter(|square| !is_searched[**square] && mines[**square]).count()).collect();
+        let unassigned = frontier.constraints.iter().map(|(squares,_)| squares.iter().filter(|square| is_searched[**square]).count()).collect();
+        return Search { frontier, mines, placed, unassigned, steps_left };
+    }
+
+    /// assigns the squares in order, calling on_layout with the mines every time they're all assigned,
+    /// until on_layout returns false,
+    /// returns false if the search ran out of steps or was stopped
+    fn run(&mut self, squares:&[usize], on_layout:&mut dyn FnMut(&[bool]) -> bool) -> bool {
+        let (square, rest) = match squares.split_first() {
+            Some(split) => split,
+            None => return on_layout(&self.mines),
+        };
+
+        for is_mine in [false, true] {
+            if self.steps_left == 0 {
+                return false;
+            }
+            self.steps_left -= 1;
+
+            //assign the square, and only go on if every constraint it's part of can still be met
+            self.mines[*square] = is_mine;
+            let mut possible = true;
+            for constraint in self.frontier.square_constraints[*square].iter() {
+                self.placed[*constraint] += is_mine as usize;
+                self.unassigned[*constraint] -= 1;
+                let n_mines = self.frontier.constraints[*constraint].1;
+                possible &= self.placed[*constraint] <= n_mines && self.placed[*constraint] + self.unassigned[*constraint] >= n_mines;
+            }
+            let keep_going = !possible || self.run(rest, on_layout);
+            for constraint in self.frontier.square_constraints[*square].iter() {
+                self.placed[*constraint] -= is_mine as usize;
+                self.unassigned[*constraint] += 1;
+            }
+            if !keep_going {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+/// works out the probability of every hidden square of the view being a mine,
+/// None if the view is too complex for even sampling to find a layout that fits it
+pub fn get_probabilities(view:&View) -> Option<Probabilities> {
+    //DATA
+    let (width,height) = view.get_dimensions();
+    let deductions = solver::solve(view);
+    let safe:HashSet<(u8,u8)> = deductions.safe.into_iter().collect();
+    let mines:HashSet<(u8,u8)> = deductions.mines.into_iter().collect();
+    let n_mines_left = view.get_n_mines().saturating_sub(mines.len());
+
+    //the frontier, and the interior squares, away from any number
+    let (frontier, n_interior) = get_frontier(view, &safe, &mines);
+
+    //the weight of every number of mines on the frontier: how many ways the rest fit in the interior
+    let interior_weights = get_interior_weights(n_mines_left, n_interior);
+
+    //count the layouts, or sample them if there are too many
+    let (frontier_probabilities, interior_probability, exact) = match count_layouts(&frontier, &interior_weights, n_mines_left, n_interior, MAX_STEPS) {
+        Some((frontier_probabilities, interior_probability)) => (frontier_probabilities, interior_probability, true),
+        None => {
+            let (frontier_probabilities, interior_probability) = sample_layouts(&frontier, &interior_weights, n_mines_left, n_interior)?;
+            (frontier_probabilities, interior_probability, false)
+        },
+    };
+
+    //put them all together
+    let mut cells:Vec<Option<f64>> = vec![None; width as usize * height as usize];
+    for (x_pos,y_pos) in view.get_hidden() {
+        cells[y_pos as usize * width as usize + x_pos as usize] = Some(
+            if safe.contains(&(x_pos,y_pos)) {0.0}
+            else if mines.contains(&(x_pos,y_pos)) {1.0}
+            else {
+                match frontier.squares.binary_search_by_key(&(y_pos,x_pos), |(x_pos,y_pos)| (*y_pos,*x_pos)) {
+                    Ok(index) => frontier_probabilities[index],
+                    Err(_) => interior_probability,
+                }
+            }
+        );
+    }
+    return Some(Probabilities { width, height, cells, exact });
+}
+
+/// gets the frontier of the view, leaving out the squares known to be safe or mines,
+/// and the number of interior squares (those that aren't known and aren't on the frontier)
+fn get_frontier(view:&View, safe:&HashSet<(u8,u8)>, mines:&HashSet<(u8,u8)>) -> (Frontier, usize) {
+    //DATA
+    let constraints = solver::get_constraints(view, safe, mines);
+    let mut squares:Vec<(u8,u8)> = constraints.iter().flat_map(|constraint| constraint.squares.iter().copied()).collect();
+    squares.sort_by_key(|(x_pos,y_pos)| (*y_pos,*x_pos));
+    squares.dedup();
+
+    let n_interior = view.get_hidden().iter().filter(|square| !safe.contains(square) && !mines.contains(square) && squares.binary_search_by_key(&(square.1,square.0), |(x_pos,y_pos)| (*y_pos,*x_pos)).is_err()).count();
+    let index_of = |square:&(u8,u8)| squares.binary_search_by_key(&(square.1,square.0), |(x_pos,y_pos)| (*y_pos,*x_pos)).unwrap_or_default();
+    let constraints:Vec<(Vec<usize>, usize)> = constraints.iter().map(|constraint| (constraint.squares.iter().map(index_of).collect(), constraint.n_mines)).collect();
+    let mut square_constraints:Vec<Vec<usize>> = vec![Vec::new(); squares.len()];
+    for (index, (constraint_squares,_)) in constraints.iter().enumerate() {
+        constraint_squares.iter().for_each(|square| square_constraints[*square].push(index));
+    }
+    return (Frontier { squares, constraints, square_constraints }, n_interior);
+}
+
+/// gets, for every number of mines from 0 to n_mines on the frontier, how many ways the rest of the mines fit in the interior,
+/// scaled so that the largest is 1
+fn get_interior_weights(n_mines:usize, n_interior:usize) -> Vec<f64> {
+    //DATA
+    let mut ln_factorials:Vec<f64> = vec![0.0; n_interior + 1];
+    for n in 1..=n_interior {
+        ln_factorials[n] = ln_factorials[n - 1] + (n as f64).ln();
+    }
+
+    let ln_weights:Vec<f64> = (0..=n_mines).map(|n_frontier_mines| match n_mines - n_frontier_mines {
+        n_interior_mines if n_interior_mines > n_interior => f64::NEG_INFINITY,
+        n_interior_mines => ln_factorials[n_interior] - ln_factorials[n_interior_mines] - ln_factorials[n_interior - n_interior_mines],
+    }).collect();
+    let max = ln_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    return ln_weights.into_iter().map(|ln_weight| (ln_weight - max).exp()).collect();
+}
+
+/// counts the layouts of every group of frontier squares that share numbers,
+/// and combines them into the probabilities of every frontier square and of every interior square,
+/// None if there are too many to count in max_steps
+fn count_layouts(frontier:&Frontier, interior_weights:&[f64], n_mines:usize, n_interior:usize, max_steps:usize) -> Option<(Vec<f64>, f64)> {
+    //DATA
+    let n_squares = frontier.squares.len();
+    let mut steps_left = max_steps;
+    let mut groups:Vec<Group> = Vec::new();
+
+    for squares in get_groups(frontier) {
+        let mut counts:Vec<f64> = vec![0.0; squares.len() + 1];
+        let mut tallies:Vec<Vec<f64>> = vec![vec![0.0; squares.len()]; squares.len() + 1];
+        let mut search = Search::new(frontier, vec![false; n_squares], &squares, steps_left);
+        let finished = search.run(&squares, &mut |mines| {
+            let n_group_mines = squares.iter().filter(|square| mines[**square]).count();
+            counts[n_group_mines] += 1.0;
+            for (index, square) in squares.iter().enumerate() {
+                tallies[n_group_mines][index] += mines[*square] as u8 as f64;
+            }
+            return true;
+        });
+        if !finished {
+            return None;
+        }
+        steps_left = search.steps_left;
+
+        //scaling a group's counts doesn't change any probability, and keeps them from overflowing
+        let max = counts.iter().copied().fold(0.0, f64::max).max(1.0);
+        counts.iter_mut().for_each(|count| *count /= max);
+        tallies.iter_mut().flatten().for_each(|tally| *tally /= max);
+        groups.push(Group { squares, counts, tallies });
+    }
+
+    //the weight of every number of mines in the groups before each group (ahead), and in the groups from each one on along with the interior (behind),
+    //every one scaled on its own to keep it from overflowing
+    let mut ahead:Vec<Vec<f64>> = vec![vec![0.0; n_mines + 1]];
+    ahead[0][0] = 1.0;
+    for Group { counts, .. } in groups.iter() {
+        let mut next = vec![0.0; n_mines + 1];
+        for (n_before, weight) in ahead.last()?.iter().enumerate() {
+            for (n_group_mines, count) in counts.iter().enumerate().filter(|(n_group_mines,_)| n_before + n_group_mines <= n_mines) {
+                next[n_before + n_group_mines] += weight * count;
+            }
+        }
+        ahead.push(normalize(next));
+    }
+    let mut behind:Vec<Vec<f64>> = vec![interior_weights.to_vec()];
+    for Group { counts, .. } in groups.iter().rev() {
+        let after = behind.last()?;
+        let next = (0..=n_mines).map(|n_before| counts.iter().enumerate()
+            .filter(|(n_group_mines,_)| n_before + n_group_mines <= n_mines)
+            .map(|(n_group_mines, count)| count * after[n_before + n_group_mines])
+            .sum()
+        ).collect();
+        behind.push(normalize(next));
+    }
+    behind.reverse();
+
+    //the probability of every frontier square, from the layouts of its group, weighted by the ways the rest of the mines fit
+    let mut probabilities:Vec<f64> = vec![0.0; n_squares];
+    for (index, Group { squares, counts, tallies }) in groups.iter().enumerate() {
+        let (before, after) = (&ahead[index], &behind[index + 1]);
+        let mut total = 0.0;
+        let mut group_probabilities:Vec<f64> = vec![0.0; squares.len()];
+        for (n_before, weight) in before.iter().enumerate().filter(|(_, weight)| **weight > 0.0) {
+            for (n_group_mines, count) in counts.iter().enumerate().filter(|(n_group_mines,_)| n_before + n_group_mines <= n_mines) {
+                let weight = weight * after[n_before + n_group_mines];
+                total += weight * count;
+                for (probability, tally) in group_probabilities.iter_mut().zip(tallies[n_group_mines].iter()) {
+                    *probability += weight * tally;
+                }
+            }
+        }
+        for (square, probability) in squares.iter().zip(group_probabilities) {
+            probabilities[*square] = if total > 0.0 {probability / total} else {0.0};
+        }
+    }
+
+    //the probability of an interior square, from the expected number of mines left for the interior
+    let before = ahead.last()?;
+    let total:f64 = (0..=n_mines).map(|n_frontier_mines| before[n_frontier_mines] * interior_weights[n_frontier_mines]).sum();
+    let expected:f64 = (0..=n_mines).map(|n_frontier_mines| before[n_frontier_mines] * interior_weights[n_frontier_mines] * (n_mines - n_frontier_mines) as f64).sum();
+    let interior_probability = if n_interior > 0 && total > 0.0 {expected / total / n_interior as f64} else {0.0};
+
+    return Some((probabilities, interior_probability));
+}
+
+/// estimates the probabilities of every frontier square and of every interior square by sampling layouts:
+/// starting from any layout that fits, blocks of neighbouring squares are laid out again at random,
+/// in proportion to how many ways the rest of the mines fit,
+/// None if no layout that fits can be found
+fn sample_layouts(frontier:&Frontier, interior_weights:&[f64], n_mines:usize, n_interior:usize) -> Option<(Vec<f64>, f64)> {
+    //DATA
+    let n_squares = frontier.squares.len();
+    let mut rng = ChaCha8Rng::seed_from_u64(SAMPLE_SEED);
+    let mut tallies:Vec<f64> = vec![0.0; n_squares];
+    let mut interior_tally = 0.0;
+
+    if n_squares == 0 {
+        return None;
+    }
+    let mut mines = get_start_layout(frontier, interior_weights)?;
+
+    for n_sample in 0..BURN_IN + N_SAMPLES {
+        //lay out the squares around a random one again
+        let mut block = get_block(frontier, rng.gen_range(0..n_squares), BLOCK_SIZE);
+        block.sort_unstable();
+
+        let n_other_mines = mines.iter().enumerate().filter(|(square, is_mine)| **is_mine && block.binary_search(square).is_err()).count();
+        let mut layouts:Vec<(Vec<bool>, f64)> = Vec::new();
+        let mut search = Search::new(frontier, mines.clone(), &block, MAX_STEPS);
+        search.run(&block, &mut |layout| {
+            let n_block_mines = block.iter().filter(|square| layout[**square]).count();
+            let weight = interior_weights.get(n_other_mines + n_block_mines).copied().unwrap_or_default();
+            if weight > 0.0 {
+                layouts.push((block.iter().map(|square| layout[*square]).collect(), weight));
+            }
+            return true;
+        });
+
+        //pick one in proportion to its weight
+        let total:f64 = layouts.iter().map(|(_, weight)| weight).sum();
+        let mut target = rng.gen::<f64>() * total;
+        if let Some((layout,_)) = layouts.iter().find(|(_, weight)| {target -= weight; target <= 0.0}).or(layouts.last()) {
+            for (square, is_mine) in block.iter().zip(layout) {
+                mines[*square] = *is_mine;
+            }
+        }
+
+        //count it
+        if n_sample >= BURN_IN {
+            mines.iter().zip(tallies.iter_mut()).for_each(|(is_mine, tally)| *tally += *is_mine as u8 as f64);
+            if n_interior > 0 {
+                interior_tally += (n_mines - mines.iter().filter(|is_mine| **is_mine).count()) as f64 / n_interior as f64;
+            }
+        }
+    }
+
+    return Some((
+        tallies.into_iter().map(|tally| tally / N_SAMPLES as f64).collect(),
+        interior_tally / N_SAMPLES as f64,
+    ));
+}
+
+/// finds a layout of the whole frontier to start sampling from, group by group:
+/// a few layouts of every group with different numbers of mines, then one of them from each group
+/// so that the rest of the mines fit in the interior,
+/// None if none is found: the view contradicts itself, or a group has too many squares to lay out in MAX_STEPS
+fn get_start_layout(frontier:&Frontier, interior_weights:&[f64]) -> Option<Vec<bool>> {
+    //DATA
+    let n_squares = frontier.squares.len();
+    //every group nearest first, so a number is met or broken soon after its first square is laid out, rather than rows later
+    let groups:Vec<Vec<usize>> = get_groups(frontier).into_iter().map(|group| get_block(frontier, group[0], group.len())).collect();
+    // for every group, a layout (of its squares) with every number of mines found
+    let mut options:Vec<Vec<(usize, Vec<bool>)>> = Vec::new();
+
+    for squares in groups.iter() {
+        let mut layouts:Vec<(usize, Vec<bool>)> = Vec::new();
+        let mut n_layouts = 0;
+        let mut search = Search::new(frontier, vec![false; n_squares], squares, MAX_STEPS);
+        search.run(squares, &mut |layout| {
+            let n_group_mines = squares.iter().filter(|square| layout[**square]).count();
+            if !layouts.iter().any(|(n,_)| *n == n_group_mines) {
+                layouts.push((n_group_mines, squares.iter().map(|square| layout[*square]).collect()));
+            }
+            n_layouts += 1;
+            return n_layouts < MAX_START_LAYOUTS;
+        });
+        if layouts.is_empty() {
+            return None;
+        }
+        options.push(layouts);
+    }
+
+    //which of them to pick to get every number of mines on the frontier, group by group
+    let n_mines = interior_weights.len() - 1;
+    let mut picks:Vec<Vec<Option<usize>>> = Vec::new();
+    let mut reachable:Vec<bool> = vec![false; n_mines + 1];
+    reachable[0] = true;
+    for layouts in options.iter() {
+        let mut next:Vec<Option<usize>> = vec![None; n_mines + 1];
+        for n_before in (0..=n_mines).filter(|n_before| reachable[*n_before]) {
+            for (index, (n_group_mines,_)) in layouts.iter().enumerate() {
+                if n_before + n_group_mines <= n_mines && next[n_before + n_group_mines].is_none() {
+                    next[n_before + n_group_mines] = Some(index);
+                }
+            }
+        }
+        reachable = next.iter().map(Option::is_some).collect();
+        picks.push(next);
+    }
+
+    //a number of mines the rest fit in the interior with, and the layouts that get there
+    let mut n_left = (0..=n_mines).find(|n_frontier_mines| reachable[*n_frontier_mines] && interior_weights[*n_frontier_mines] > 0.0)?;
+    let mut mines:Vec<bool> = vec![false; n_squares];
+    for ((squares, layouts), picks) in groups.iter().zip(options.iter()).zip(picks.iter()).rev() {
+        let (n_group_mines, layout) = &layouts[picks[n_left]?];
+        for (square, is_mine) in squares.iter().zip(layout) {
+            mines[*square] = *is_mine;
+        }
+        n_left -= n_group_mines;
+    }
+    return Some(mines);
+}
+
+/// gets up to max_size squares of the frontier, starting from the given one and spreading through the constraints,
+/// nearest first
+fn get_block(frontier:&Frontier, start:usize, max_size:usize) -> Vec<usize> {
+    //DATA
+    let mut block:Vec<usize> = vec![start];
+    let mut next = 0;
+
+    while let Some(square) = block.get(next).copied() {
+        next += 1;
+        for constraint in frontier.square_constraints[square].iter() {
+            for other in frontier.constraints[*constraint].0.iter() {
+                if block.len() >= max_size {
+                    return block;
+                }
+                if !block.contains(other) {
+                    block.push(*other);
+                }
+            }
+        }
+    }
+    return block;
+}
+
+/// splits the frontier's squares into groups that share no constraints, each in order
+fn get_groups(frontier:&Frontier) -> Vec<Vec<usize>> {
+    //DATA
+    let mut group_of:Vec<Option<usize>> = vec![None; frontier.squares.len()];
+    let mut groups:Vec<Vec<usize>> = Vec::new();
+
+    //flood fill from every square that's in no group yet, through the constraints
+    for start in 0..frontier.squares.len() {
+        if group_of[start].is_some() {
+            continue;
+        }
+        let mut group:Vec<usize> = vec![start];
+        group_of[start] = Some(groups.len());
+        let mut next = 0;
+        while let Some(square) = group.get(next).copied() {
+            next += 1;
+            for constraint in frontier.square_constraints[square].iter() {
+                for other in frontier.constraints[*constraint].0.iter() {
+                    if group_of[*other].is_none() {
+                        group_of[*other] = Some(groups.len());
+                        group.push(*other);
+                    }
+                }
+            }
+        }
+        group.sort_unstable();
+        groups.push(group);
+    }
+    return groups;
+}
+
+/// scales the weights so that the largest is 1 (unless they're all 0)
+fn normalize(weights:Vec<f64>) -> Vec<f64> {
+    let max = weights.iter().copied().fold(0.0, f64::max);
+    if max <= 0.0 {
+        return weights;
+    }
+    return weights.into_iter().map(|weight| weight / max).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+    use crate::game::field::{Difficulty, Field, State};
+    use crate::game::solver::{tests::get_view, Cell};
+
+    /// works out the probability of every hidden square of the view being a mine by going through every layout
+    fn brute_force(view:&View) -> Vec<((u8,u8), f64)> {
+        //DATA
+        let hidden = view.get_hidden();
+        let (width,height) = view.get_dimensions();
+        let mut tallies:Vec<f64> = vec![0.0; hidden.len()];
+        let mut n_layouts = 0.0;
+
+        for layout in 0..1_u32 << hidden.len() {
+            if layout.count_ones() as usize != view.get_n_mines() {
+                continue;
+            }
+            let is_mine = |x_pos:isize, y_pos:isize| hidden.iter().position(|square| *square == (x_pos as u8, y_pos as u8)).is_some_and(|index| layout >> index & 1 == 1);
+            let fits = (0..height as isize).flat_map(|y_pos| (0..width as isize).map(move |x_pos| (x_pos,y_pos))).all(|(x_pos,y_pos)| match view.get_cell(x_pos, y_pos) {
+                Some(Cell::REVEALED(n)) => get_neighbours(x_pos, y_pos).filter(|(x_pos,y_pos)| view.get_cell(*x_pos, *y_pos) == Some(Cell::HIDDEN) && is_mine(*x_pos, *y_pos)).count() == n as usize,
+                _ => true,
+            });
+            if fits {
+                n_layouts += 1.0;
+                tallies.iter_mut().enumerate().for_each(|(index, tally)| *tally += (layout >> index & 1) as f64);
+            }
+        }
+        return hidden.into_iter().zip(tallies.into_iter().map(|tally| tally / n_layouts)).collect();
+    }
+
+    /// the positions around the given one
+    fn get_neighbours(x_pos:isize, y_pos:isize) -> impl Iterator<Item = (isize,isize)> {
+        return (-1..=1).flat_map(move |dy| (-1..=1).map(move |dx| (x_pos + dx, y_pos + dy))).filter(move |position| *position != (x_pos,y_pos));
+    }
+
+    /// views with several groups on the frontier, and interior squares, so the total mine count matters
+    fn get_views() -> Vec<View> {
+        return vec![
+            //two groups, on either side of an interior
+            get_view(&[
+                "#.####.#",
+                "#.####.#",
+                "#.####.#",
+            ], &[(0,1), (3,0), (4,2), (7,0)]),
+            //a 1-2-1 along a wall, and a corner
+            get_view(&[
+                "#####",
+                ".....",
+                "...##",
+                "...##",
+            ], &[(0,0), (2,0), (4,3)]),
+            //a group that can take more or fewer mines, against the mines left for the interior
+            get_view(&[
+                "######",
+                "#...##",
+                "#...##",
+                "######",
+            ], &[(0,0), (1,0), (5,1), (0,3), (4,3)]),
+            //nothing revealed yet
+            get_view(&[
+                "####",
+                "####",
+            ], &[(1,0), (2,1)]),
+        ];
+    }
+
+    #[test]
+    fn exact_probabilities_match_brute_force() {
+        for view in get_views() {
+            let probabilities = get_probabilities(&view).unwrap();
+            assert!(probabilities.is_exact());
+            for ((x_pos,y_pos), expected) in brute_force(&view) {
+                let probability = probabilities.get(x_pos, y_pos).unwrap();
+                assert!((probability - expected).abs() < 1e-9, "{:?} at ({},{}): {} instead of {}", view, x_pos, y_pos, probability, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn revealed_squares_have_no_probability() {
+        let view = get_views().remove(0);
+        let probabilities = get_probabilities(&view).unwrap();
+        assert_eq!(probabilities.get_dimensions(), (8,3));
+        assert_eq!(probabilities.get(1, 0), None);
+        assert_eq!(probabilities.get(8, 0), None);
+    }
+
+    #[test]
+    fn counting_gives_up_when_out_of_steps() {
+        let view = get_views().remove(0);
+        let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+        let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+        assert!(count_layouts(&frontier, &interior_weights, view.get_n_mines(), n_interior, 1).is_none());
+        assert!(count_layouts(&frontier, &interior_weights, view.get_n_mines(), n_interior, MAX_STEPS).is_some());
+    }
+
+    #[test]
+    fn sampled_probabilities_are_close_to_brute_force() {
+        for view in get_views().into_iter().filter(|view| view.get_hidden().len() < view.get_dimensions().0 as usize * view.get_dimensions().1 as usize) {
+            let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+            let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+            let (frontier_probabilities, interior_probability) = sample_layouts(&frontier, &interior_weights, view.get_n_mines(), n_interior).unwrap();
+            for ((x_pos,y_pos), expected) in brute_force(&view) {
+                let probability = match frontier.squares.iter().position(|square| *square == (x_pos,y_pos)) {
+                    Some(index) => frontier_probabilities[index],
+                    None => interior_probability,
+                };
+                assert!((probability - expected).abs() < 0.02, "{:?} at ({},{}): {} instead of {}", view, x_pos, y_pos, probability, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn start_layout_fits_every_constraint_and_the_mines_left() {
+        for view in get_views() {
+            let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+            let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+            let mines = get_start_layout(&frontier, &interior_weights).unwrap();
+            for (squares, n_mines) in frontier.constraints.iter() {
+                assert_eq!(squares.iter().filter(|square| mines[**square]).count(), *n_mines);
+            }
+            assert!(interior_weights[mines.iter().filter(|is_mine| **is_mine).count()] > 0.0);
+        }
+    }
+
+    #[test]
+    fn start_layout_picks_group_layouts_that_leave_room_for_the_interior() {
+        //the 1s share the middle square: one mine there, or one on each end, and with 2 mines and no interior only the ends fit
+        let view = View::new(5, 1, 2, vec![Cell::HIDDEN, Cell::REVEALED(1), Cell::HIDDEN, Cell::REVEALED(1), Cell::HIDDEN]).unwrap();
+        let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+        assert_eq!(n_interior, 0);
+        let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+        assert_eq!(get_start_layout(&frontier, &interior_weights), Some(vec![true, false, true]));
+    }
+
+    #[test]
+    fn start_layout_is_found_for_long_groups() {
+        //an expert field with a third of its safe squares revealed here and there, leaving a group of well over 100 squares
+        let mut field = Field::new();
+        field.initialize(Difficulty::EXPERT, 5);
+        field.populate(0, 0, false);
+        let mut safe:Vec<(u8,u8)> = field.get_grid().iter().flatten().filter(|square| !square.is_mine()).map(|square| square.get_position()).collect();
+        safe.shuffle(&mut ChaCha8Rng::seed_from_u64(5));
+        for (x_pos,y_pos) in safe.iter().take(safe.len() * 35 / 100) {
+            field.get_square_at_mut(*x_pos as isize, *y_pos as isize).unwrap().set_state(State::VISIBLE);
+        }
+        let view = View::from_field(&field);
+        let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+        assert!(get_groups(&frontier).iter().any(|group| group.len() > 100));
+        let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+        let mines = get_start_layout(&frontier, &interior_weights).unwrap();
+        for (squares, n_mines) in frontier.constraints.iter() {
+            assert_eq!(squares.iter().filter(|square| mines[**square]).count(), *n_mines);
+        }
+    }
+
+    #[test]
+    fn start_layout_fails_on_a_contradiction() {
+        //a 2 next to a single hidden square
+        let view = View::new(3, 1, 2, vec![Cell::REVEALED(2), Cell::HIDDEN, Cell::HIDDEN]).unwrap();
+        let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+        let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+        assert!(get_start_layout(&frontier, &interior_weights).is_none());
+
+        //numbers that fit, but need more mines than there are
+        let view = View::new(5, 1, 1, vec![Cell::HIDDEN, Cell::REVEALED(1), Cell::REVEALED(0), Cell::REVEALED(1), Cell::HIDDEN]).unwrap();
+        let (frontier, n_interior) = get_frontier(&view, &HashSet::new(), &HashSet::new());
+        let interior_weights = get_interior_weights(view.get_n_mines(), n_interior);
+        assert!(get_start_layout(&frontier, &interior_weights).is_none());
+    }
+}
@@ -0,0 +1,301 @@
+use std::{
+    error::Error, //better errors
+    fs, path::{Path, PathBuf}, //files
+    time::Duration,
+};
+
+use super::config;
+use super::engine::{Game, Rules};
+use super::field::{Difficulty, Field, State};
+
+//DATA
+const SAVE_HEADER: &str = "rust-cli-minesweeper save v1";
+const AUTOSAVE_FILE: &str = "autosave";
+const SAVE_FILE: &str = "saved-game";
+
+/*
+ * a saved game is a plain text file:
+ * rust-cli-minesweeper save v1
+ * difficulty <name> <width> <height> <mines>
+ * seed <seed>
+ * rules <safe_neighbours: 0 or 1> <strict_flags: 0 or 1> <marks: 0 or 1> <practice: 0 or 1> <no_guess: 0 or 1>
+ * actions <number of actions applied>
+ * elapsed <time played in milliseconds>
+ * populated <whether the mines are placed yet: 0 or 1>
+ * practiced <whether a lost game was undone, or the game was restarted: 0 or 1> (optional, 0 if it's missing)
+ * hints <number of hints given> (optional, 0 if it's missing)
+ * first-check <x> <y> (optional, only once the mines are placed, the square they were placed around)
+ * followed by one line per row of the field, with one character per square:
+ * '-' hidden, '*' hidden mine, 'f' flagged, 'F' flagged mine, '?' marked, 'M' marked mine, ' ' visible, 'X' visible mine,
+ * and once the game is lost '!' detonated mine, 'x' wrong flag
+ */
+
+/// gets the path games are autosaved to
+pub fn get_autosave_path() -> Option<PathBuf> {
+    return Some(config::get_data_dir()?.join(AUTOSAVE_FILE));
+}
+/// gets the path games are saved to when no path is given
+pub fn get_default_save_path() -> Option<PathBuf> {
+    return Some(config::get_data_dir()?.join(SAVE_FILE));
+}
+
+/// saves the full state of a game to the file at the given path, creating its directory if needed
+pub fn save_game(game:&Game, path:&Path) -> Result<(), Box<dyn Error>> {
+    //DATA
+    let field = game.field();
+    let difficulty = field.get_difficulty();
+    let (width,height) = difficulty.get_dimensions();
+    let rules = game.rules();
+    let mut contents = String::new();
+
+    //header
+    contents.push_str(&format!("{}\n", SAVE_HEADER));
+    contents.push_str(&format!("difficulty {} {} {} {}\n", difficulty.get_name(), width, height, difficulty.get_n_mines()));
+    contents.push_str(&format!("seed {}\n", field.get_seed()));
+    contents.push_str(&format!("rules {} {} {} {} {}\n", rules.safe_neighbours as u8, rules.strict_flags as u8, rules.marks as u8, rules.practice as u8, rules.no_guess as u8));
+    contents.push_str(&format!("actions {}\n", game.get_n_actions()));
+    contents.push_str(&format!("elapsed {}\n", game.elapsed().as_millis()));
+    contents.push_str(&format!("populated {}\n", field.is_populated() as u8));
+    contents.push_str(&format!("practiced {}\n", game.is_practiced() as u8));
+    contents.push_str(&format!("hints {}\n", game.get_n_hints()));
+    if let Some((x_pos,y_pos)) = field.get_first_check() {
+        contents.push_str(&format!("first-check {} {}\n", x_pos, y_pos));
+    }
+
+    //field
+    for row in field.get_grid().iter() {
+        for square in row.iter() {
+            contents.push(match (square.get_state(), square.is_mine()) {
+                (State::HIDDEN, false) => '-',
+                (State::HIDDEN, true) => '*',
+                (State::FLAGGED, false) => 'f',
+                (State::FLAGGED, true) => 'F',
+                (State::MARKED, false) => '?',
+                (State::MARKED, true) => 'M',
+                (State::VISIBLE, false) => ' ',
+                (State::VISIBLE, true) => 'X',
+                (State::DETONATED, _) => '!',
+                (State::WRONG_FLAG, _) => 'x',
+            });
+        }
+        contents.push('\n');
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)?;
+    return Ok(());
+}
+
+/// loads the game saved in the file at the given path
+///
+/// Errors:
+/// the file can't be read
+/// the file isn't a saved game, or was saved by an unknown version
+/// the saved field doesn't match the saved difficulty
+pub fn load_game(path:&Path) -> Result<Game, Box<dyn Error>> {
+    //DATA
+    let contents = fs::read_to_string(path)?;
+    let mut lines = contents.lines().peekable();
+
+    //header
+    if lines.next() != Some(SAVE_HEADER) {
+        return Err(format!("{} isn't a saved game (or was saved by another version)", path.display()).into());
+    }
+    let difficulty_values = read_value(lines.next(), "difficulty")?;
+    let difficulty_values:Vec<&str> = difficulty_values.split(' ').collect();
+    if difficulty_values.len() != 4 {
+        return Err("malformed difficulty".into());
+    }
+    let (width,height):(u8,u8) = (difficulty_values[1].parse()?, difficulty_values[2].parse()?);
+    let difficulty = match Difficulty::from_name(difficulty_values[0]) {
+        Some(difficulty) => difficulty,
+        None => Difficulty::CUSTOM { width, height, mines: difficulty_values[3].parse()? },
+    };
+    difficulty.validate()?;
+    let seed:u64 = read_value(lines.next(), "seed")?.parse()?;
+    let rules_values:Vec<&str> = read_value(lines.next(), "rules")?.split(' ').collect();
+    let rules = Rules {
+        safe_neighbours: rules_values.first() == Some(&"1"),
+        strict_flags: rules_values.get(1) == Some(&"1"),
+        marks: rules_values.get(2) == Some(&"1"),
+        practice: rules_values.get(3) == Some(&"1"),
+        no_guess: rules_values.get(4) == Some(&"1"),
+    };
+    let n_actions:usize = read_value(lines.next(), "actions")?.parse()?;
+    let elapsed = Duration::from_millis(read_value(lines.next(), "elapsed")?.parse()?);
+    let populated = read_value(lines.next(), "populated")? == "1";
+    let practiced = match lines.peek() {
+        Some(line) if line.starts_with("practiced") => read_value(lines.next(), "practiced")? == "1",
+        _ => false,
+    };
+    let n_hints:usize = match lines.peek() {
+        Some(line) if line.starts_with("hints") => read_value(lines.next(), "hints")?.parse()?,
+        _ => 0,
+    };
+    let first_check:Option<(u8,u8)> = match lines.peek() {
+        Some(line) if line.starts_with("first-check") => {
+            let values = read_value(lines.next(), "first-check")?;
+            let (x_pos,y_pos) = values.split_once(' ').ok_or("malformed first check")?;
+            Some((x_pos.parse()?, y_pos.parse()?))
+        },
+        _ => None,
+    };
+
+    //field
+    let mut field = Field::new();
+    field.initialize(difficulty, seed);
+    let mut mines:Vec<(u8,u8)> = Vec::new();
+    let mut states:Vec<(u8,u8,State)> = Vec::new();
+    for y_pos in 0..height {
+        let row:Vec<char> = lines.next().ok_or("the saved field has too few rows")?.chars().collect();
+        if row.len() != width as usize {
+            return Err(format!("row {} of the saved field should be {} squares wide", y_pos, width).into());
+        }
+        for (x_pos,c) in row.into_iter().enumerate() {
+            let (state, is_mine) = match c {
+                '-' => (State::HIDDEN, false),
+                '*' => (State::HIDDEN, true),
+                'f' => (State::FLAGGED, false),
+                'F' => (State::FLAGGED, true),
+                '?' => (State::MARKED, false),
+                'M' => (State::MARKED, true),
+                ' ' => (State::VISIBLE, false),
+                'X' => (State::VISIBLE, true),
+                '!' => (State::DETONATED, true),
+                'x' => (State::WRONG_FLAG, false),
+                _ => return Err(format!("unknown square '{}' in the saved field", c).into()),
+            };
+            if is_mine {mines.push((x_pos as u8, y_pos))}
+            states.push((x_pos as u8, y_pos, state));
+        }
+    }
+    if populated {
+        field.place_mines(&mines);
+        field.set_first_check(first_check);
+    }
+    for (x_pos,y_pos,state) in states {
+        if let Some(square) = field.get_square_at_mut(x_pos as isize, y_pos as isize) {
+            square.set_state(state);
+        }
+        if state == State::FLAGGED {
+            field.increment_n_flags();
+        }
+    }
+
+    return Ok(Game::restore(field, rules, n_actions, n_hints, elapsed, practiced));
+}
+
+/// reads the value of a "key value" line
+fn read_value<'a>(line:Option<&'a str>, key:&str) -> Result<&'a str, String> {
+    return line
+        .and_then(|line| line.strip_prefix(key))
+        .and_then(|value| value.strip_prefix(' '))
+        .ok_or(format!("the saved game is missing its {}", key));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::engine::{Action, Outcome};
+    use crate::game::field::GameStatus;
+
+    /// gets a path in the temporary directory that no other test uses
+    fn get_test_path(name:&str) -> PathBuf {
+        return std::env::temp_dir().join(format!("rust-cli-minesweeper-test-{}-{}", std::process::id(), name));
+    }
+
+    /// gets the state of every square of the field, and whether it's a mine, row by row
+    fn get_squares(field:&Field) -> Vec<(State, bool)> {
+        return field.get_grid().iter().flatten().map(|square| (*square.get_state(), square.is_mine())).collect();
+    }
+
+    #[test]
+    fn round_trip() {
+        //a practiced game, with flags, a mark and a hint
+        let rules = Rules { safe_neighbours: false, strict_flags: true, marks: true, practice: true, no_guess: true };
+        let mut game = Game::new(Difficulty::BEGINNER, 42, rules).unwrap();
+        game.apply(Action::CHECK(4,4)).unwrap();
+        let mines = game.field().get_mines().clone();
+        let (flagged, marked, detonated) = (mines[0], mines[1], mines[2]);
+        game.apply(Action::FLAG(flagged.0, flagged.1)).unwrap();
+        game.apply(Action::FLAG(marked.0, marked.1)).unwrap();
+        game.apply(Action::FLAG(marked.0, marked.1)).unwrap();
+        assert_eq!(game.apply(Action::CHECK(detonated.0, detonated.1)), Ok(Outcome::LOST));
+        game.undo().unwrap();
+        game.hint().unwrap();
+        assert!(game.is_practiced());
+
+        let path = get_test_path("round-trip");
+        let elapsed = Duration::from_millis(game.elapsed().as_millis() as u64);
+        save_game(&game, &path).unwrap();
+        let loaded = load_game(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.field().get_difficulty(), &Difficulty::BEGINNER);
+        assert_eq!(loaded.field().get_seed(), 42);
+        assert_eq!(loaded.field().get_first_check(), Some((4,4)));
+        assert_eq!(loaded.field().get_mines().len(), 10);
+        assert_eq!(loaded.field().get_n_flags(), 1);
+        assert_eq!(get_squares(loaded.field()), get_squares(game.field()));
+        assert!(!loaded.rules().safe_neighbours && loaded.rules().strict_flags && loaded.rules().marks && loaded.rules().practice && loaded.rules().no_guess);
+        assert!(loaded.is_practiced());
+        assert_eq!(*loaded.status(), GameStatus::PLAYING);
+        assert_eq!(loaded.get_n_actions(), game.get_n_actions());
+        assert_eq!(loaded.get_n_hints(), 1);
+        assert!(loaded.elapsed() >= elapsed);
+    }
+
+    #[test]
+    fn round_trip_before_the_first_check() {
+        let game = Game::new(Difficulty::CUSTOM { width: 7, height: 5, mines: 6 }, 3, Rules::default()).unwrap();
+        let path = get_test_path("not-started");
+        save_game(&game, &path).unwrap();
+        let mut loaded = load_game(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!loaded.field().is_populated());
+        assert_eq!(loaded.field().get_first_check(), None);
+        assert_eq!(loaded.field().get_difficulty(), &Difficulty::CUSTOM { width: 7, height: 5, mines: 6 });
+        //the mines are placed from the seed once it's played
+        loaded.apply(Action::CHECK(0,0)).unwrap();
+        assert_eq!(loaded.field().get_mines().len(), 6);
+    }
+
+    #[test]
+    fn optional_lines_can_be_missing() {
+        let path = get_test_path("optional");
+        fs::write(&path, format!("{}\ndifficulty custom 3 1 1\nseed 5\nrules 1 0 0 0 0\nactions 1\nelapsed 1500\npopulated 1\n  *\n", SAVE_HEADER)).unwrap();
+        let loaded = load_game(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!loaded.is_practiced());
+        assert_eq!(loaded.get_n_hints(), 0);
+        assert_eq!(loaded.field().get_first_check(), None);
+        assert_eq!(loaded.field().get_mines(), &vec![(2,0)]);
+        assert_eq!(loaded.get_n_actions(), 1);
+        assert!(loaded.elapsed() >= Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn rejects_bad_saves() {
+        let path = get_test_path("bad");
+        let header = format!("{}\ndifficulty custom 3 1 1\nseed 5\nrules 1 0 0 0\nactions 0\nelapsed 0\npopulated 0\n", SAVE_HEADER);
+        for contents in [
+            String::from("not a save\n"),
+            format!("{}\ndifficulty custom 3 1\n", SAVE_HEADER),
+            format!("{}\nseed 5\n", SAVE_HEADER),
+            format!("{}--\n", header),
+            format!("{}-#-\n", header),
+            header.clone(),
+        ] {
+            fs::write(&path, &contents).unwrap();
+            assert!(load_game(&path).is_err(), "{:?}", contents);
+        }
+        fs::write(&path, format!("{}-*-\n", header)).unwrap();
+        assert!(load_game(&path).is_ok());
+        fs::remove_file(&path).unwrap();
+        assert!(load_game(&path).is_err());
+    }
+}
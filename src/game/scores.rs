@@ -0,0 +1,212 @@
+use std::{
+    error::Error, //better errors
+    fs::{self, OpenOptions}, io::Write, path::PathBuf, //files
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::config;
+use super::field::Difficulty;
+
+//DATA
+/// how many scores are shown per difficulty
+pub const TOP_N: usize = 10;
+const SCORES_FILE: &str = "scores";
+const SCORES_HEADER: &str = "# rust-cli-minesweeper scores v1: difficulty, width, height, mines, time (ms), date (unix time), seed, name, first check (x,y, optional)";
+
+/// a won game
+#[derive(Clone, PartialEq, Debug)]
+pub struct Score {
+    pub difficulty: Difficulty,
+    /// how long the game took
+    pub time: Duration,
+    /// when the game was won, in seconds since the unix epoch
+    pub date: u64,
+    pub seed: u64,
+    pub name: String,
+    /// the square the mines were placed around, needed along with the seed to play the same field again
+    pub first_check: Option<(u8,u8)>,
+}
+impl Score {
+    /// creates a new score for a game won just now
+    pub fn new(difficulty:Difficulty, time:Duration, seed:u64, first_check:Option<(u8,u8)>, name:&str) -> Score {
+        //tabs and newlines would break the scores file
+        let name:String = name.chars().filter(|c| !c.is_control()).collect();
+        return Score {
+            difficulty,
+            time: Duration::from_millis(time.as_millis() as u64), //the scores file only keeps milliseconds
+            date: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            seed,
+            name: if name.trim().is_empty() {String::from("anonymous")} else {String::from(name.trim())},
+            first_check,
+        };
+    }
+
+    /// gets the date the game was won on, as YYYY-MM-DD
+    pub fn get_date(&self) -> String {
+        //days since 1970-01-01 to a civil date, see http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let days = (self.date / 86400) as i64 + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era/1460 + day_of_era/36524 - day_of_era/146096) / 365;
+        let day_of_year = day_of_era - (365*year_of_era + year_of_era/4 - year_of_era/100);
+        let mp = (5*day_of_year + 2) / 153;
+        let day = day_of_year - (153*mp + 2)/5 + 1;
+        let month = if mp < 10 {mp + 3} else {mp - 9};
+        let year = year_of_era + era*400 + if month <= 2 {1} else {0};
+        return format!("{:04}-{:02}-{:02}", year, month, day);
+    }
+
+    /// turns the score into a line of the scores file
+    fn to_line(&self) -> String {
+        let (width,height) = self.difficulty.get_dimensions();
+        let mut line = format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.difficulty.get_name(), width, height, self.difficulty.get_n_mines(),
+            self.time.as_millis(), self.date, self.seed, self.name,
+        );
+        if let Some((x_pos,y_pos)) = self.first_check {
+            line.push_str(&format!("\t{},{}", x_pos, y_pos));
+        }
+        return line;
+    }
+    /// reads a score from a line of the scores file, None if it's malformed
+    fn from_line(line:&str) -> Option<Score> {
+        let mut values = line.splitn(9, '\t');
+        let name = values.next()?;
+        let width:u8 = values.next()?.parse().ok()?;
+        let height:u8 = values.next()?.parse().ok()?;
+        let mines:usize = values.next()?.parse().ok()?;
+        let difficulty = match Difficulty::from_name(name) {
+            Some(difficulty) => difficulty,
+            None => Difficulty::CUSTOM { width, height, mines },
+        };
+
+        return Some(Score {
+            difficulty,
+            time: Duration::from_millis(values.next()?.parse().ok()?),
+            date: values.next()?.parse().ok()?,
+            seed: values.next()?.parse().ok()?,
+            name: String::from(values.next()?),
+            //older scores don't have it
+            first_check: match values.next() {
+                Some(first_check) => {
+                    let (x_pos,y_pos) = first_check.split_once(',')?;
+                    Some((x_pos.parse().ok()?, y_pos.parse().ok()?))
+                },
+                None => None,
+            },
+        });
+    }
+}
+
+/// gets the path of the scores file
+fn get_scores_path() -> Result<PathBuf, Box<dyn Error>> {
+    return Ok(config::get_data_dir().ok_or("couldn't find a directory to keep scores in, is $HOME set?")?.join(SCORES_FILE));
+}
+
+/// loads every score saved so far, there are none if the scores file doesn't exist yet
+pub fn load_scores() -> Result<Vec<Score>, Box<dyn Error>> {
+    let path = get_scores_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    return Ok(fs::read_to_string(&path)?
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(Score::from_line)
+        .collect());
+}
+
+/// adds a score to the scores file, creating it if needed
+pub fn save_score(score:&Score) -> Result<(), Box<dyn Error>> {
+    let path = get_scores_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    if is_new {
+        writeln!(file, "{}", SCORES_HEADER)?;
+    }
+    writeln!(file, "{}", score.to_line())?;
+    return Ok(());
+}
+
+/// gets the best (fastest) TOP_N scores for the given difficulty, best first
+pub fn get_top_scores(scores:&[Score], difficulty:Difficulty) -> Vec<&Score> {
+    let mut top_scores:Vec<&Score> = scores.iter().filter(|score| score.difficulty == difficulty).collect();
+    top_scores.sort_by_key(|score| (score.time, score.date));
+    top_scores.truncate(TOP_N);
+    return top_scores;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// makes a score won at the given time, on the given date
+    fn get_score(difficulty:Difficulty, time_ms:u64, date:u64) -> Score {
+        return Score { difficulty, time: Duration::from_millis(time_ms), date, seed: 7, name: String::from("ada"), first_check: Some((4,4)) };
+    }
+
+    #[test]
+    fn round_trip() {
+        for difficulty in [Difficulty::BEGINNER, Difficulty::CUSTOM { width: 12, height: 7, mines: 20 }] {
+            let score = Score { name: String::from("ada lovelace"), ..get_score(difficulty, 12345, 1792281600) };
+            assert_eq!(Score::from_line(&score.to_line()), Some(score));
+        }
+
+        //scores from before the first check was recorded don't have it
+        let score = Score { first_check: None, ..get_score(Difficulty::BEGINNER, 12345, 1792281600) };
+        assert_eq!(score.to_line(), "beginner\t9\t9\t10\t12345\t1792281600\t7\tada");
+        assert_eq!(Score::from_line(&score.to_line()), Some(score));
+    }
+
+    #[test]
+    fn names_are_cleaned_up() {
+        assert_eq!(Score::new(Difficulty::BEGINNER, Duration::from_micros(1500), 0, None, " a\tb\n ").name, "ab");
+        assert_eq!(Score::new(Difficulty::BEGINNER, Duration::ZERO, 0, None, "  ").name, "anonymous");
+        assert_eq!(Score::new(Difficulty::BEGINNER, Duration::from_micros(1500), 0, None, "a").time, Duration::from_millis(1));
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        for line in [
+            "",
+            "beginner\t9\t9\t10\t12345\t1792281600\t7",
+            "beginner\t9\t9\t10\tfast\t1792281600\t7\tada",
+            "beginner\tnine\t9\t10\t12345\t1792281600\t7\tada",
+            "custom\t9\t9\t-1\t12345\t1792281600\t7\tada",
+            "beginner\t9\t9\t10\t12345\t1792281600\t7\tada\t4",
+            "beginner\t9\t9\t10\t12345\t1792281600\t7\tada\t4,x",
+        ] {
+            assert_eq!(Score::from_line(line), None, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(get_score(Difficulty::BEGINNER, 0, 0).get_date(), "1970-01-01");
+        assert_eq!(get_score(Difficulty::BEGINNER, 0, 951782400).get_date(), "2000-02-29");
+        assert_eq!(get_score(Difficulty::BEGINNER, 0, 1735603200 + 86399).get_date(), "2024-12-31");
+        assert_eq!(get_score(Difficulty::BEGINNER, 0, 1792281600).get_date(), "2026-10-18");
+    }
+
+    #[test]
+    fn top_scores() {
+        let mut scores:Vec<Score> = (0..15).map(|n| get_score(Difficulty::BEGINNER, 1000 * (15 - n), n)).collect();
+        scores.push(get_score(Difficulty::INTERMEDIATE, 1, 0));
+        //a tie on time goes to the earlier win
+        scores.push(get_score(Difficulty::BEGINNER, 1000, 0));
+
+        let top_scores = get_top_scores(&scores, Difficulty::BEGINNER);
+        assert_eq!(top_scores.len(), TOP_N);
+        assert!(top_scores.iter().all(|score| score.difficulty == Difficulty::BEGINNER));
+        assert_eq!((top_scores[0].time, top_scores[0].date), (Duration::from_millis(1000), 0));
+        assert_eq!((top_scores[1].time, top_scores[1].date), (Duration::from_millis(1000), 14));
+        assert!(top_scores.windows(2).all(|pair| pair[0].time <= pair[1].time));
+        assert_eq!(get_top_scores(&scores, Difficulty::INTERMEDIATE).len(), 1);
+        assert!(get_top_scores(&scores, Difficulty::ADVANCED).is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::{BTreeSet, HashSet};
+
+use super::field::{Field, GameStatus, State, NEIGHBOURS};
+
+/// what the player can see of a square
+#[allow(non_camel_case_types)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cell {
+    /// not revealed yet, flags and question marks are hidden too as they can be wrong
+    HIDDEN,
+    /// revealed, with the number of mines around it
+    REVEALED(u8),
+}
+
+/**
+ * the player-visible projection of a field:
+ * which squares are revealed and their numbers, and how many mines there are in total,
+ * the solver only ever works on this so it can't cheat
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub struct View {
+    width: u8,
+    height: u8,
+    n_mines: usize,
+    /// row by row, from the top left corner
+    cells: Vec<Cell>,
+}
+impl View {
+    /// creates a view of a width x height field with n_mines mines, from its cells given row by row
+    ///
+    /// Errors:
+    /// there aren't width x height cells
+    pub fn new(width:u8, height:u8, n_mines:usize, cells:Vec<Cell>) -> Result<View, String> {
+        if cells.len() != width as usize * height as usize {
+            return Err(format!("a {}x{} view needs {} cells, not {}", width, height, width as usize * height as usize, cells.len()));
+        }
+        return Ok(View { width, height, n_mines, cells });
+    }
+
+    /// gets what the player can see of the field
+    pub fn from_field(field:&Field) -> View {
+        let (width,height) = field.get_dimensions();
+        let cells = field.get_grid().iter().flatten().map(|square| match square.get_state() {
+            State::VISIBLE if !square.is_mine() => Cell::REVEALED(square.get_danger() as u8),
+            _ => Cell::HIDDEN,
+        }).collect();
+        return View { width, height, n_mines: field.get_n_mines(), cells };
+    }
+
+    //getters
+    /// get the dimensions as (width,height)
+    pub fn get_dimensions(&self) -> (u8,u8) {(self.width,self.height)}
+    /// get the total number of mines
+    pub fn get_n_mines(&self) -> usize {self.n_mines}
+    /// get the cell at the given position, None if it's not on the field
+    pub fn get_cell(&self, x_pos:isize, y_pos:isize) -> Option<Cell> {
+        if x_pos < 0 || y_pos < 0 || x_pos >= self.width as isize || y_pos >= self.height as isize {
+            return None;
+        }
+        return Some(self.cells[y_pos as usize * self.width as usize + x_pos as usize]);
+    }
+    /// get the positions of every hidden cell
+    pub fn get_hidden(&self) -> Vec<(u8,u8)> {
+        return self.get_positions().filter(|(x_pos,y_pos)| self.get_cell(*x_pos as isize, *y_pos as isize) == Some(Cell::HIDDEN)).collect();
+    }
+    /// get the positions of the hidden cells around the given position
+    pub fn get_hidden_neighbours(&self, x_pos:u8, y_pos:u8) -> Vec<(u8,u8)> {
+        return NEIGHBOURS.iter()
+            .map(|(dx,dy)| (x_pos as isize + dx, y_pos as isize + dy))
+            .filter(|(x_pos,y_pos)| self.get_cell(*x_pos, *y_pos) == Some(Cell::HIDDEN))
+            .map(|(x_pos,y_pos)| (x_pos as u8, y_pos as u8))
+            .collect();
+    }
+    /// get every position on the field, row by row
+    fn get_positions(&self) -> impl Iterator<Item = (u8,u8)> + '_ {
+        return (0..self.height).flat_map(move |y_pos| (0..self.width).map(move |x_pos| (x_pos,y_pos)));
+    }
+}
+
+/// what the solver is certain of
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Deductions {
+    /// hidden squares that can't be mines, so they're safe to check
+    pub safe: Vec<(u8,u8)>,
+    /// hidden squares that must be mines
+    pub mines: Vec<(u8,u8)>,
+}
+impl Deductions {
+    /// returns whether nothing could be deduced
+    pub fn is_empty(&self) -> bool {
+        return self.safe.is_empty() && self.mines.is_empty();
+    }
+}
+
+/// a revealed number's constraint: exactly n_mines of these hidden squares are mines
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Constraint {
+    pub squares: BTreeSet<(u8,u8)>,
+    pub n_mines: usize,
+}
+
+/// gets the constraints of every revealed number that borders a hidden square,
+/// leaving out the squares already known to be safe or mines (and counting the mines)
+pub fn get_constraints(view:&View, safe:&HashSet<(u8,u8)>, mines:&HashSet<(u8,u8)>) -> Vec<Constraint> {
+    //DATA
+    let mut constraints:HashSet<Constraint> = HashSet::new();
+
+    for (x_pos,y_pos) in view.get_positions() {
+        let danger = match view.get_cell(x_pos as isize, y_pos as isize) {
+            Some(Cell::REVEALED(danger)) => danger as usize,
+            _ => continue,
+        };
+        let neighbours = view.get_hidden_neighbours(x_pos, y_pos);
+        let n_known_mines = neighbours.iter().filter(|square| mines.contains(square)).count();
+        let squares:BTreeSet<(u8,u8)> = neighbours.into_iter().filter(|square| !safe.contains(square) && !mines.contains(square)).collect();
+        if !squares.is_empty() {
+            constraints.insert(Constraint { squares, n_mines: danger.saturating_sub(n_known_mines) });
+        }
+    }
+
+    return constraints.into_iter().collect();
+}
+
+/// finds every hidden square of the view that's certainly safe or certainly a mine,
+/// repeating these deductions until nothing new is found:
+/// - trivial deductions: a number with as many hidden squares around it as mines left, or no mines left
+/// - subset deductions: a number whose hidden squares are all around another number too,
+///   so the other number's remaining squares have the difference of their mines
+/// - pair deductions: two overlapping numbers where one has so many more mines than the other,
+///   that all of its squares outside the overlap must be mines (and the other's are safe)
+/// - the mine count: no mines left, or as many left as hidden squares
+pub fn solve(view:&View) -> Deductions {
+    //DATA
+    let mut safe:HashSet<(u8,u8)> = HashSet::new();
+    let mut mines:HashSet<(u8,u8)> = HashSet::new();
+
+    loop {
+        let n_known = safe.len() + mines.len();
+        let constraints = get_constraints(view, &safe, &mines);
+
+        //trivial deductions
+        for constraint in constraints.iter() {
+            if constraint.n_mines == 0 {
+                safe.extend(constraint.squares.iter());
+            } else if constraint.n_mines == constraint.squares.len() {
+                mines.extend(constraint.squares.iter());
+            }
+        }
+
+        //subset and pair deductions
+        if safe.len() + mines.len() == n_known {
+            for a in constraints.iter() {
+                for b in constraints.iter().filter(|b| *b != a && !a.squares.is_disjoint(&b.squares)) {
+                    let only_b:Vec<&(u8,u8)> = b.squares.difference(&a.squares).collect();
+                    let only_a:Vec<&(u8,u8)> = a.squares.difference(&b.squares).collect();
+
+                    if only_a.is_empty() {
+                        //a is a subset of b, so b's other squares hold the rest of its mines
+                        if b.n_mines == a.n_mines {
+                            safe.extend(only_b);
+                        } else if b.n_mines - a.n_mines.min(b.n_mines) == only_b.len() {
+                            mines.extend(only_b);
+                        }
+                    } else if b.n_mines >= a.n_mines && b.n_mines - a.n_mines == only_b.len() {
+                        //the overlap holds at most a's mines, so b needs every one of its own squares to be a mine,
+                        //which leaves all of a's mines in the overlap
+                        mines.extend(only_b);
+                        safe.extend(only_a);
+                    }
+                }
+            }
+        }
+
+        //the mine count
+        let unknown:Vec<(u8,u8)> = view.get_hidden().into_iter().filter(|square| !safe.contains(square) && !mines.contains(square)).collect();
+        let n_mines_left = view.get_n_mines().saturating_sub(mines.len());
+        if !unknown.is_empty() && n_mines_left == 0 {
+            safe.extend(unknown);
+        } else if !unknown.is_empty() && n_mines_left == unknown.len() {
+            mines.extend(unknown);
+        }
+
+        //stop once nothing new is found
+        if safe.len() + mines.len() == n_known {
+            break;
+        }
+    }
+
+    //sorted row by row
+    let mut safe:Vec<(u8,u8)> = safe.into_iter().collect();
+    let mut mines:Vec<(u8,u8)> = mines.into_iter().collect();
+    safe.sort_by_key(|(x_pos,y_pos)| (*y_pos,*x_pos));
+    mines.sort_by_key(|(x_pos,y_pos)| (*y_pos,*x_pos));
+    return Deductions { safe, mines };
+}
+
+/// returns whether the (populated) field can be cleared from a check at the given position without guessing,
+/// by checking every square the solver finds safe until it finds none, ignoring any flags on it
+pub fn can_clear(field:&Field, x_pos:u8, y_pos:u8) -> bool {
+    //DATA
+    let mut field = field.clone();
+    let (width,height) = field.get_dimensions();
+    for (x_pos,y_pos) in (0..height).flat_map(|y_pos| (0..width).map(move |x_pos| (x_pos,y_pos))) {
+        if let Some(square) = field.get_square_at_mut(x_pos as isize, y_pos as isize) {
+            square.set_state(State::HIDDEN);
+        }
+    }
+
+    field.reveal(x_pos as isize, y_pos as isize);
+    loop {
+        if field.get_status(false) != GameStatus::PLAYING {
+            return field.get_status(false) == GameStatus::WON;
+        }
+        let deductions = solve(&View::from_field(&field));
+        if deductions.safe.is_empty() {
+            return false;
+        }
+        for (x_pos,y_pos) in deductions.safe {
+            field.reveal(x_pos as isize, y_pos as isize);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::game::field::Difficulty;
+
+    /// builds the view of a field with mines at the given positions,
+    /// from its rows with '.' for revealed squares and '#' for hidden ones
+    pub(crate) fn get_view(rows:&[&str], mines:&[(u8,u8)]) -> View {
+        let (width,height) = (rows[0].len() as u8, rows.len() as u8);
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width, height, mines: mines.len() }, 0);
+        field.place_mines(mines);
+        for (y_pos,row) in rows.iter().enumerate() {
+            for (x_pos,_) in row.char_indices().filter(|(_,c)| *c == '.') {
+                if let Some(square) = field.get_square_at_mut(x_pos as isize, y_pos as isize) {
+                    square.set_state(State::VISIBLE);
+                }
+            }
+        }
+        return View::from_field(&field);
+    }
+
+    /// gets every layout of n_mines mines among the hidden squares that agrees with every revealed number
+    fn get_layouts(view:&View) -> Vec<HashSet<(u8,u8)>> {
+        //DATA
+        let hidden = view.get_hidden();
+        let mut layouts:Vec<HashSet<(u8,u8)>> = Vec::new();
+        let mut mines:Vec<(u8,u8)> = Vec::new();
+
+        fn place(view:&View, hidden:&[(u8,u8)], n_left:usize, mines:&mut Vec<(u8,u8)>, layouts:&mut Vec<HashSet<(u8,u8)>>) {
+            if n_left == 0 {
+                let layout:HashSet<(u8,u8)> = mines.iter().copied().collect();
+                let fits = view.get_positions().all(|(x_pos,y_pos)| match view.get_cell(x_pos as isize, y_pos as isize) {
+                    Some(Cell::REVEALED(danger)) => view.get_hidden_neighbours(x_pos, y_pos).iter().filter(|square| layout.contains(square)).count() == danger as usize,
+                    _ => true,
+                });
+                if fits {
+                    layouts.push(layout);
+                }
+                return;
+            }
+            for (index, square) in hidden.iter().enumerate() {
+                mines.push(*square);
+                place(view, &hidden[index + 1..], n_left - 1, mines, layouts);
+                mines.pop();
+            }
+        }
+        place(view, &hidden, view.get_n_mines(), &mut mines, &mut layouts);
+        return layouts;
+    }
+
+    #[test]
+    fn view_needs_every_cell() {
+        assert!(View::new(2, 2, 1, vec![Cell::HIDDEN; 3]).is_err());
+        assert!(View::new(2, 2, 1, vec![Cell::HIDDEN; 4]).is_ok());
+    }
+
+    #[test]
+    fn view_hides_flags_and_marks() {
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width: 3, height: 1, mines: 1 }, 0);
+        field.place_mines(&[(2,0)]);
+        field.get_square_at_mut(0, 0).unwrap().set_state(State::VISIBLE);
+        field.get_square_at_mut(1, 0).unwrap().set_state(State::MARKED);
+        field.get_square_at_mut(2, 0).unwrap().set_state(State::FLAGGED);
+        let view = View::from_field(&field);
+        assert_eq!(view.get_cell(0, 0), Some(Cell::REVEALED(0)));
+        assert_eq!(view.get_hidden(), vec![(1,0), (2,0)]);
+        assert_eq!(view.get_n_mines(), 1);
+    }
+
+    #[test]
+    fn trivial_deductions() {
+        //the 1s only touch the corner, and the 0s clear the column next to them
+        let view = get_view(&[
+            "#..###",
+            "...###",
+            "...###",
+        ], &[(0,0), (5,0), (5,2)]);
+        assert_eq!(solve(&view), Deductions { safe: vec![(3,0), (3,1), (3,2)], mines: vec![(0,0)] });
+    }
+
+    #[test]
+    fn subset_deductions() {
+        //the 1 in the corner's squares are all around the number next to it, which has the same mines
+        let view = get_view(&[
+            "####",
+            "..##",
+        ], &[(0,0), (3,0), (3,1)]);
+        assert_eq!(solve(&view), Deductions { safe: vec![(2,0), (2,1)], mines: vec![] });
+
+        //or 2 more, so the squares only around it are mines
+        let view = get_view(&[
+            "####",
+            "..##",
+        ], &[(0,0), (2,0), (2,1), (3,0)]);
+        assert_eq!(solve(&view), Deductions { safe: vec![], mines: vec![(2,0), (2,1)] });
+    }
+
+    #[test]
+    fn pair_deductions() {
+        //the 1 and the 3 share 2 squares, so the 3's own 2 squares are mines and the 1's are safe
+        let view = get_view(&[
+            "####",
+            "#..#",
+        ], &[(1,0), (3,0), (3,1)]);
+        assert_eq!(solve(&view), Deductions { safe: vec![(0,0), (0,1)], mines: vec![(3,0), (3,1)] });
+    }
+
+    #[test]
+    fn mine_count_deductions() {
+        //once the 1's mine is found there are none left
+        let view = get_view(&[".###"], &[(1,0)]);
+        assert_eq!(solve(&view), Deductions { safe: vec![(2,0), (3,0)], mines: vec![(1,0)] });
+
+        //or as many left as squares
+        let view = get_view(&[".###"], &[(1,0), (2,0), (3,0)]);
+        assert_eq!(solve(&view), Deductions { safe: vec![], mines: vec![(1,0), (2,0), (3,0)] });
+
+        //nothing revealed, and nothing can be told apart
+        let view = get_view(&["####"], &[(1,0)]);
+        assert!(solve(&view).is_empty());
+    }
+
+    #[test]
+    fn deductions_agree_with_every_layout() {
+        let mut n_deductions = 0;
+        for seed in 0..200 {
+            //a small field with a few squares revealed
+            let mut field = Field::new();
+            field.initialize(Difficulty::CUSTOM { width: 5, height: 4, mines: 5 }, seed);
+            field.populate(0, 0, false);
+            field.reveal(0, 0);
+            let safe_squares:Vec<(u8,u8)> = field.get_grid().iter().flatten().filter(|square| !square.is_mine()).map(|square| square.get_position()).collect();
+            let (x_pos,y_pos) = safe_squares[seed as usize % safe_squares.len()];
+            field.reveal(x_pos as isize, y_pos as isize);
+
+            let view = View::from_field(&field);
+            let layouts = get_layouts(&view);
+            assert!(!layouts.is_empty());
+            let deductions = solve(&view);
+            for layout in layouts.iter() {
+                assert!(deductions.safe.iter().all(|square| !layout.contains(square)), "seed {}: {:?} aren't all safe in {:?}", seed, deductions.safe, layout);
+                assert!(deductions.mines.iter().all(|square| layout.contains(square)), "seed {}: {:?} aren't all mines in {:?}", seed, deductions.mines, layout);
+            }
+            n_deductions += deductions.safe.len() + deductions.mines.len();
+        }
+        //so it isn't just agreeing by never deducing anything
+        assert!(n_deductions > 200, "only {} deductions", n_deductions);
+    }
+
+    #[test]
+    fn can_clear_without_guessing() {
+        //the 0 opens everything up to the 1 next to the mine
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width: 3, height: 1, mines: 1 }, 0);
+        field.place_mines(&[(2,0)]);
+        assert!(can_clear(&field, 0, 0));
+
+        //a 1 touching 3 squares with 1 mine among them can't be told apart
+        let mut field = Field::new();
+        field.initialize(Difficulty::CUSTOM { width: 2, height: 2, mines: 1 }, 0);
+        field.place_mines(&[(0,0)]);
+        assert!(!can_clear(&field, 1, 1));
+    }
+}
@@ -0,0 +1,91 @@
+use std::{env, io::{self, IsTerminal}};
+
+use super::field::{Square, State};
+use super::glyphs::GlyphSet;
+use super::probabilities::Probabilities;
+
+//DATA
+/// ANSI styles of the numbers 1 to 8, classic minesweeper colours (0 is never drawn)
+const NUMBERS: [&str; 9] = ["", "94", "32", "91", "34", "31", "36", "35", "90"];
+const FLAG: &str = "1;93";
+const MARKED: &str = "95";
+const MINE: &str = "1";
+const DETONATED_MINE: &str = "1;97;41";
+const WRONG_FLAG: &str = "1;91;9";
+/// drawn over the square's own style
+const HIGHLIGHT: &str = "7";
+/// ANSI styles of the chance of a mine, from certainly safe, through every fifth, to certainly a mine
+const HEAT: [&str; 7] = ["1;92", "92", "32", "93", "33", "91", "1;31"];
+
+/// how squares are drawn: the glyphs, and the colours
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub glyphs: GlyphSet,
+    /// whether colours are drawn at all
+    colours: bool,
+}
+impl Theme {
+    /// creates a theme drawing the given glyphs,
+    /// in colour unless stdout isn't a terminal, or NO_COLOR is set (see https://no-color.org)
+    pub fn detect(glyphs:GlyphSet) -> Theme {
+        let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        return Theme { glyphs, colours: io::stdout().is_terminal() && !no_color };
+    }
+
+    /// gets the icon of the square, wrapped in the ANSI codes for its colour
+    pub fn paint(&self, square:&Square) -> String {
+        //DATA
+        let icon = square.get_icon(&self.glyphs);
+
+        if !self.colours {
+            return icon.to_string();
+        }
+        let style = match square.get_state() {
+            State::HIDDEN => "",
+            State::FLAGGED => FLAG,
+            State::MARKED => MARKED,
+            State::WRONG_FLAG => WRONG_FLAG,
+            State::DETONATED => DETONATED_MINE,
+            State::VISIBLE if square.is_mine() => MINE,
+            State::VISIBLE => NUMBERS[square.get_danger()],
+        };
+        if style.is_empty() {
+            return icon.to_string();
+        }
+        return format!("\x1b[{}m{}\x1b[0m", style, icon);
+    }
+
+    /// gets a single character for the chance of the square being a mine, in tenths, wrapped in the ANSI codes for its heat:
+    /// a certain safe square is drawn as '.', and a certain mine with the glyph of a mine
+    pub fn paint_probability(&self, probability:f64) -> String {
+        let (icon, style) = match probability {
+            p if p <= 0.0 => ('.', HEAT[0]),
+            p if p >= 1.0 => (self.glyphs.mine, HEAT[6]),
+            p => (char::from_digit(((p * 10.0) as u32).min(9), 10).unwrap_or('9'), HEAT[1 + ((p * 5.0) as usize).min(4)]),
+        };
+
+        if !self.colours {
+            return icon.to_string();
+        }
+        return format!("\x1b[{}m{}\x1b[0m", style, icon);
+    }
+
+    /// gets the chance of the square being a mine drawn by paint_probability, in place of its icon,
+    /// revealed squares are left empty so their numbers aren't mistaken for chances
+    pub fn paint_chance(&self, square:&Square, probabilities:&Probabilities) -> String {
+        let (x_pos,y_pos) = square.get_position();
+        return match probabilities.get(x_pos, y_pos) {
+            Some(probability) => self.paint_probability(probability),
+            None => self.glyphs.numbers[0].to_string(),
+        };
+    }
+
+    /// gets the icon of the square like paint, but in reverse video so it stands out,
+    /// or the highlight glyph in its place if colours aren't drawn
+    pub fn paint_highlighted(&self, square:&Square) -> String {
+        if !self.colours {
+            return self.glyphs.highlight.to_string();
+        }
+        return format!("\x1b[{}m{}\x1b[0m", HIGHLIGHT, self.paint(square));
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    error::Error, //better errors
+    io::{self, Stdout, Write}, //io interactions
+    panic, sync::Arc, time::Duration,
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers, MouseButton, MouseEventKind},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use super::{apply_command, cli_utils, end_game, get_confirmation, get_hint, get_probabilities, print_autosave_hint, save, Ending};
+use super::config::Config;
+use super::engine::{Action, Game};
+use super::field::GameStatus;
+use super::parser::Command;
+use super::probabilities::Probabilities;
+use super::theme::Theme;
+
+//DATA
+const HELP: [&str; 2] = [
+    "arrows/hjkl: move  space/left click: check  f/right click: flag  d/middle click: chord  u: undo  r: redo",
+    "?: hint  p: chances of mines  R: restart  n: new game  a: reveal all  s: save  q: quit",
+];
+/// how long to wait for a key before redrawing anyway, to keep the timer ticking
+const TICK: Duration = Duration::from_millis(250);
+
+type PanicHook = Box<dyn Fn(&panic::PanicHookInfo) + Sync + Send + 'static>;
+
+/// run the program, a raw mode terminal frontend over the game engine,
+/// where a cursor is moved around the field and squares are checked and flagged with single keys or mouse clicks
+/// until the game is over or the player quits
+pub fn run(config: &mut Config) -> Result<Ending, Box<dyn Error>> {
+    // a finished game (e.g. a loaded one) can't be played any further
+    if *config.game.status() != GameStatus::PLAYING {
+        end_game(&config.game, &config.theme);
+        return Ok(Ending::GAME_OVER);
+    }
+
+    {
+        //DATA
+        let _terminal = RawTerminal::enter()?;
+        let mut screen = Screen::new(&config.game, config.theme);
+        let (width,height) = config.game.field().get_dimensions();
+        let mut cursor:(u8,u8) = (width/2, height/2);
+        let mut message = String::new();
+        // the command waiting for the player to confirm it, if any
+        let mut pending:Option<Command> = None;
+        // the chances of mines drawn over the field, until the next key
+        let mut probabilities:Option<Probabilities> = None;
+
+        //for every key
+        loop {
+            screen.draw(&config.game, cursor, &message, probabilities.as_ref())?;
+
+            //wait for a key, redrawing every tick
+            if !event::poll(TICK)? {
+                continue;
+            }
+            let key = match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => key,
+                //a click on the field moves the cursor there and acts on the square
+                Event::Mouse(mouse) if *config.game.status() == GameStatus::PLAYING => {
+                    let button = match mouse.kind {
+                        MouseEventKind::Down(button) => button,
+                        _ => continue,
+                    };
+                    let (x_pos,y_pos) = match Screen::get_square_at(&config.game, mouse.column, mouse.row) {
+                        Some(position) => position,
+                        None => continue,
+                    };
+                    cursor = (x_pos,y_pos);
+                    pending = None;
+                    probabilities = None;
+                    message = apply(&mut config.game, Command::ACTION(match button {
+                        MouseButton::Left => Action::CHECK(x_pos, y_pos),
+                        MouseButton::Right => Action::FLAG(x_pos, y_pos),
+                        MouseButton::Middle => Action::CHORD(x_pos, y_pos),
+                    }));
+                    continue;
+                },
+                Event::Resize(..) => {screen.invalidate(); continue;},
+                _ => continue,
+            };
+
+            //game over, any key leaves (but u takes back the losing move of a practice game)
+            if *config.game.status() != GameStatus::PLAYING {
+                if key.code == KeyCode::Char('u') && config.game.rules().practice {
+                    message = apply(&mut config.game, Command::UNDO);
+                    continue;
+                }
+                break;
+            }
+
+            //y goes ahead with the command waiting to be confirmed, any other key calls it off
+            probabilities = None;
+            if let Some(command) = pending.take() {
+                message = match key.code {
+                    KeyCode::Char('y') | KeyCode::Char('Y') => apply(&mut config.game, command),
+                    _ => String::new(),
+                };
+                continue;
+            }
+
+            //handle key
+            let command = match key.code {
+                KeyCode::Left | KeyCode::Char('h') => {cursor.0 = cursor.0.saturating_sub(1); None},
+                KeyCode::Right | KeyCode::Char('l') => {cursor.0 = (cursor.0 + 1).min(width - 1); None},
+                KeyCode::Up | KeyCode::Char('k') => {cursor.1 = cursor.1.saturating_sub(1); None},
+                KeyCode::Down | KeyCode::Char('j') => {cursor.1 = (cursor.1 + 1).min(height - 1); None},
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                KeyCode::Char(' ') | KeyCode::Enter => Some(Command::ACTION(Action::CHECK(cursor.0, cursor.1))),
+                KeyCode::Char('f') => Some(Command::ACTION(Action::FLAG(cursor.0, cursor.1))),
+                KeyCode::Char('d') => Some(Command::ACTION(Action::CHORD(cursor.0, cursor.1))),
+                KeyCode::Char('u') => Some(Command::UNDO),
+                KeyCode::Char('r') => Some(Command::REDO),
+                KeyCode::Char('?') => {
+                    //the cursor goes to the square the hint points out
+                    match get_hint(&mut config.game) {
+                        Ok((hint, square)) => {
+                            message = hint;
+                            cursor = square.unwrap_or(cursor);
+                        },
+                        Err(e) => message = e.to_string(),
+                    }
+                    None
+                },
+                KeyCode::Char('p') => {
+                    match get_probabilities(&mut config.game) {
+                        Ok(chances) => {
+                            message = cli_utils::get_probabilities_key(&config.theme, &chances);
+                            probabilities = Some(chances);
+                        },
+                        Err(e) => message = e,
+                    }
+                    None
+                },
+                KeyCode::Char('R') => Some(Command::RESTART),
+                KeyCode::Char('n') => Some(Command::NEW(None)),
+                KeyCode::Char('a') => Some(Command::REVEAL_ALL),
+                KeyCode::Char('s') => {
+                    message = save_game(&config.game);
+                    None
+                },
+                KeyCode::Char('q') | KeyCode::Esc => break,
+                _ => None,
+            };
+
+            //apply it, once it's confirmed if it throws away the game in progress
+            if let Some(command) = command {
+                message = match get_confirmation(&config.game, &command) {
+                    Some(question) => {
+                        pending = Some(command);
+                        String::from(question)
+                    },
+                    None => apply(&mut config.game, command),
+                };
+            }
+        }
+    }
+
+    // back to the normal terminal
+    match config.game.status() {
+        GameStatus::PLAYING => {
+            print_autosave_hint();
+            Ok(Ending::QUIT)
+        },
+        _ => {
+            end_game(&config.game, &config.theme);
+            Ok(Ending::GAME_OVER)
+        },
+    }
+}
+
+/// applies a command to the game and autosaves it, returning a message saying what happened
+fn apply(game: &mut Game, command: Command) -> String {
+    let result = apply_command(game, command);
+    return match (result, game.status()) {
+        (Err(e), _) => e.to_string(),
+        (Ok(()), GameStatus::PLAYING) => String::new(),
+        (Ok(()), GameStatus::LOST) if game.is_given_up() => String::from("you gave up, here's where the mines were (press any key)"),
+        (Ok(()), GameStatus::LOST) if game.rules().practice => String::from("you hit a mine, you lose (press u to undo, or any other key)"),
+        (Ok(()), GameStatus::LOST) => String::from("you hit a mine, you lose (press any key)"),
+        (Ok(()), GameStatus::WON) => String::from("You win, congradulations! (press any key)"),
+    };
+}
+
+/// saves the game to the default save path, returning a message saying how it went
+fn save_game(game: &Game) -> String {
+    let path = match save::get_default_save_path() {
+        Some(path) => path,
+        None => return String::from("couldn't find a directory to save to"),
+    };
+    return match save::save_game(game, &path) {
+        Ok(()) => format!("game saved, resume it with --load {}", path.display()),
+        Err(e) => format!("couldn't save game: {}", e),
+    };
+}
+
+/// puts the terminal in raw mode on an alternate screen, reporting mouse clicks, while it's alive, and restores it when dropped,
+/// or before a panic message is printed
+struct RawTerminal {
+    /// the panic hook from before the terminal was entered, put back when it's dropped
+    previous_hook: Arc<PanicHook>,
+}
+impl RawTerminal {
+    fn enter() -> io::Result<RawTerminal> {
+        //restore the terminal before the panic message is printed, so that it can be read
+        let previous_hook:Arc<PanicHook> = Arc::new(panic::take_hook());
+        let hook = Arc::clone(&previous_hook);
+        panic::set_hook(Box::new(move |info| {
+            RawTerminal::restore();
+            hook(info);
+        }));
+
+        //made before entering, so that the terminal is restored even if entering only partly works
+        let raw_terminal = RawTerminal { previous_hook };
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture, Hide)?;
+        return Ok(raw_terminal);
+    }
+    /// takes the terminal out of raw mode and back to the normal screen
+    fn restore() {
+        let _ = execute!(io::stdout(), Show, DisableMouseCapture, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        RawTerminal::restore();
+        //put back the panic hook from before
+        let previous_hook = Arc::clone(&self.previous_hook);
+        panic::set_hook(Box::new(move |info| previous_hook(info)));
+    }
+}
+
+/**
+ * draws the game in the same layout as cli_utils::print_game_state,
+ * remembering what's been drawn so that only the squares that changed are redrawn
+ */
+struct Screen {
+    stdout: Stdout,
+    theme: Theme,
+    /// the (painted) icon drawn in every square and whether the cursor was on it, None if it has to be redrawn
+    drawn: Vec<Vec<Option<(String,bool)>>>,
+    /// whether everything has to be redrawn
+    invalidated: bool,
+}
+impl Screen {
+    fn new(game: &Game, theme: Theme) -> Screen {
+        let (width,height) = game.field().get_dimensions();
+        return Screen {
+            stdout: io::stdout(),
+            theme,
+            drawn: vec![vec![None; width as usize]; height as usize],
+            invalidated: true,
+        };
+    }
+
+    /// makes the next draw redraw everything, e.g. after the terminal was resized
+    fn invalidate(&mut self) {
+        self.invalidated = true;
+        self.drawn.iter_mut().flatten().for_each(|square| *square = None);
+    }
+
+    /// gets the position on screen of the top left square of the game's field, as (column,row)
+    fn get_board_origin(game: &Game) -> (u16,u16) {
+        return (cli_utils::BOARD_LEFT_MARGIN, cli_utils::HEADER_HEIGHT + cli_utils::get_board_top_margin(game.field()));
+    }
+
+    /// gets the square drawn at the given position on screen (column,row), None if it's not on the field
+    fn get_square_at(game: &Game, column: u16, row: u16) -> Option<(u8,u8)> {
+        let (width,height) = game.field().get_dimensions();
+        let (left,top) = Screen::get_board_origin(game);
+        let (x_pos,y_pos) = (column.checked_sub(left)?, row.checked_sub(top)?);
+        if x_pos >= width as u16 || y_pos >= height as u16 {
+            return None;
+        }
+        return Some((x_pos as u8, y_pos as u8));
+    }
+
+    /// draws the game, with the cursor on the square at the given position, and the message below it,
+    /// with the chances of the hidden squares being mines in place of the field if there are any
+    fn draw(&mut self, game: &Game, cursor: (u8,u8), message: &str, probabilities: Option<&Probabilities>) -> io::Result<()> {
+        //DATA
+        let field = game.field();
+        let (width,height) = field.get_dimensions();
+        let (left,top) = Screen::get_board_origin(game);
+
+        //the parts that never change: column labels, row numbers and borders
+        if self.invalidated {
+            queue!(self.stdout, Clear(ClearType::All))?;
+            let column_label_lines = cli_utils::get_column_label_lines(field);
+            let n_lines = column_label_lines.len() as u16;
+            for (line_number, line) in column_label_lines.iter().enumerate() {
+                let line = format!("{:margin$}#{}#", "", line, margin = left as usize - 1);
+                queue!(self.stdout, MoveTo(0, top - n_lines + line_number as u16), Print(&line))?;
+                queue!(self.stdout, MoveTo(0, top + height as u16 + 1 + line_number as u16), Print(&line))?;
+            }
+            for row in 0..height as u16 {
+                queue!(self.stdout, MoveTo(0, top + row), Print(format!("{:<margin$}#", row, margin = left as usize - 1)))?;
+                queue!(self.stdout, MoveTo(left + width as u16, top + row), Print(format!("#{:>margin$}", row, margin = left as usize - 1)))?;
+            }
+            self.invalidated = false;
+        }
+
+        //header, it has the timer so it's always redrawn
+        for (line_number, line) in cli_utils::get_header_lines(game, &self.theme).iter().enumerate() {
+            queue!(self.stdout, MoveTo(0, line_number as u16), Print(line), Clear(ClearType::UntilNewLine))?;
+        }
+
+        //the squares that changed
+        for square in field.get_grid().iter().flatten() {
+            let (x_pos,y_pos) = square.get_position();
+            let icon = match probabilities {
+                Some(probabilities) => self.theme.paint_chance(square, probabilities),
+                None => self.theme.paint(square),
+            };
+            let now = (icon, (x_pos,y_pos) == cursor);
+            let drawn = &mut self.drawn[y_pos as usize][x_pos as usize];
+            if drawn.as_ref() == Some(&now) {
+                continue;
+            }
+
+            queue!(self.stdout, MoveTo(left + x_pos as u16, top + y_pos as u16))?;
+            if now.1 {
+                queue!(self.stdout, SetAttribute(Attribute::Reverse), Print(&now.0), SetAttribute(Attribute::Reset))?;
+            } else {
+                queue!(self.stdout, Print(&now.0))?;
+            }
+            *drawn = Some(now);
+        }
+
+        //message and help
+        let bottom = top + height as u16 + 2 + cli_utils::get_board_top_margin(field);
+        queue!(self.stdout, MoveTo(0, bottom), Print(message), Clear(ClearType::UntilNewLine))?;
+        for (line_number, line) in HELP.iter().enumerate() {
+            queue!(self.stdout, MoveTo(0, bottom + 1 + line_number as u16), Print(line), Clear(ClearType::UntilNewLine))?;
+        }
+
+        return self.stdout.flush();
+    }
+}
@@ -0,0 +1,4 @@
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)] // explicit returns and SCREAMING enum variants are the house style
+//! play minesweeper in the commandline, or build other frontends on top of the game engine in game::engine
+
+pub mod game;
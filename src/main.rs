@@ -1,7 +1,39 @@
-use std::process;//allows for some better error handling
+#![allow(clippy::needless_return)] // explicit returns are the house style
+use std::{env, path::PathBuf, process};//allows for some better error handling
 
-mod game; //allows access to lib.rs
-use game::config::Config;
+use rust_cli_minesweeper::game; //allows access to lib.rs
+use game::config::{Config, Difficulty, Options};
+use game::glyphs::{GlyphSet, GLYPH_SETS};
+
+//DATA
+const USAGE: &str = "
+Usage: rust-cli-minesweeper [OPTIONS]
+
+Starts a game of minesweeper, anything not given as an option is asked for when the game starts.
+
+Options:
+    --difficulty <NAME>     beginner, intermediate, advanced or expert (or just the first letter)
+    --width <N>             width of a custom field, needs --height and --mines as well
+    --height <N>            height of a custom field, needs --width and --mines as well
+    --mines <N>             number of mines on a custom field, needs --width and --height as well
+    --seed <N>              place mines from the given seed, so the same field can be played again,
+                            as long as the first check is on the same square (shown next to the seed) with the same rules
+    --load <FILE>           resume a saved game
+    --safe-square           only keep the first checked square free of mines
+    --safe-area             keep the first checked square and its neighbours free of mines (default)
+    --strict-flags          only win once every mine (and nothing else) is flagged
+    --marks                 flagging a flag turns it into a question mark, flagging that removes it
+    --practice              allow undoing the move that hit a mine, the game then doesn't count for the high scores
+    --no-guess              place the mines so the field can be cleared from the first check without guessing,
+                            only on fields where at most 21% of the squares are mines (expert and easier)
+    --no-clear              don't clear the screen between rounds
+    --glyphs <NAME>         draw the field with ascii (default), unicode or nerd (Nerd Font) glyphs,
+                            can also be set with a 'glyphs = <NAME>' line in the config file
+                            ($XDG_CONFIG_HOME/rust-cli-minesweeper/config)
+    --tui                   play with a cursor (arrow keys or hjkl, space to check, f to flag) instead of typing commands
+    --scores                print the high scores and exit
+    --help                  print this message
+";
 
 /// main function
 /// responsibilities:
@@ -10,19 +42,55 @@ use game::config::Config;
 /// - Calling a run function in lib.rs
 /// - Handling the error if run returns an error
 fn main() {
+    // parse command line arguments
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    });
+
+    // print the high scores without playing
+    if options.show_scores {
+        if let Err(e) = game::print_scores() {
+            eprintln!("Problem reading high scores: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
     //greet user
     welcome();
 
-    // set up other configuration
-    let mut config = Config::new().unwrap_or_else(|err| {
-        eprintln!("Problem configuring program: {}", err);
-        process::exit(1);
-    });
+    //remind the player of an unfinished game
+    if options.load.is_none() {
+        if let Some(path) = game::save::get_autosave_path().filter(|path| path.exists()) {
+            println!("an unfinished game was autosaved, resume it with --load {}\n", path.display());
+        }
+    }
+
+    //play games until the player quits, or doesn't want another one
+    let mut options = options;
+    loop {
+        // set up other configuration
+        let mut config = Config::new(options.clone()).unwrap_or_else(|err| {
+            eprintln!("Problem configuring program: {}", err);
+            process::exit(1);
+        });
+
+        // run the program
+        let result = if config.tui {game::tui::run(&mut config)} else {game::run(&mut config)};
+        match result {
+            Ok(game::Ending::GAME_OVER) if game::offer_another_game() => {},
+            Ok(_) => break,
+            Err(e) => {
+                eprintln!("Application Error: {}", e); //use the eprintln! macro to output to standard error
+                process::exit(1); //exit the program with an error code
+            },
+        }
 
-    // run the program
-    if let Err(e) = game::run(&mut config) {
-        eprintln!("Application Error: {}", e); //use the eprintln! macro to output to standard error
-        process::exit(1); //exit the program with an error code
+        //the next game is a new one, on a new field
+        options.load = None;
+        options.seed = None;
     }
 
     //end of program
@@ -36,3 +104,81 @@ fn welcome() {
 
     ");
 }
+
+/// parses the command line arguments (without the program name) into options for the game
+///
+/// Errors:
+/// unknown arguments, or arguments missing their value
+/// values that aren't valid numbers or difficulty names
+/// a difficulty mixed with custom dimensions, or custom dimensions missing some of --width, --height and --mines
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    //DATA
+    let mut options = Options::default();
+    let mut width:Option<u8> = None;
+    let mut height:Option<u8> = None;
+    let mut mines:Option<usize> = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" => {
+                let name = args.next().ok_or("--difficulty needs a value")?;
+                options.difficulty = Some(Difficulty::from_name(&name).ok_or(format!("unknown difficulty '{}'", name))?);
+            },
+            "--width" => width = Some(parse_value(&arg, args.next())?),
+            "--height" => height = Some(parse_value(&arg, args.next())?),
+            "--mines" => mines = Some(parse_value(&arg, args.next())?),
+            "--seed" => options.seed = Some(parse_value(&arg, args.next())?),
+            "--load" => options.load = Some(PathBuf::from(args.next().ok_or("--load needs a value")?)),
+            "--safe-square" => options.safe_neighbours = Some(false),
+            "--safe-area" => options.safe_neighbours = Some(true),
+            "--strict-flags" => options.strict_flags = Some(true),
+            "--marks" => options.marks = true,
+            "--practice" => options.practice = true,
+            "--no-guess" => options.no_guess = true,
+            "--no-clear" => options.no_clear = true,
+            "--tui" => options.tui = true,
+            "--glyphs" => {
+                let name = args.next().ok_or("--glyphs needs a value")?;
+                options.glyphs = Some(GlyphSet::from_name(&name).ok_or(format!(
+                    "unknown glyph set '{}', use one of {}", name, GLYPH_SETS.map(|glyphs| glyphs.name).join(", ")
+                ))?);
+            },
+            "--scores" => options.show_scores = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            },
+            _ => return Err(format!("unknown argument '{}'", arg)),
+        }
+    }
+
+    //custom dimensions
+    match (width, height, mines) {
+        (None, None, None) => {},
+        (Some(width), Some(height), Some(mines)) => {
+            if options.difficulty.is_some() {
+                return Err(String::from("use either --difficulty or --width, --height and --mines, not both"));
+            }
+            options.difficulty = Some(Difficulty::CUSTOM { width, height, mines });
+        },
+        _ => return Err(String::from("a custom field needs all of --width, --height and --mines")),
+    }
+
+    //a saved game already has its field
+    if options.load.is_some() && (options.difficulty.is_some() || options.seed.is_some()) {
+        return Err(String::from("--load can't be used with a difficulty, custom dimensions or a seed"));
+    }
+
+    //make sure a field can actually be made
+    if let Some(difficulty) = options.difficulty {
+        difficulty.validate()?;
+    }
+
+    return Ok(options);
+}
+
+/// parses the value following the argument arg
+fn parse_value<T: std::str::FromStr>(arg:&str, value:Option<String>) -> Result<T, String> {
+    let value = value.ok_or(format!("{} needs a value", arg))?;
+    return value.parse().map_err(|_| format!("invalid value '{}' for {}", value, arg));
+}